@@ -0,0 +1,187 @@
+//! The canonical biome names, their aliases and which biomes sit inside others.
+
+/// Matches every biome.
+pub const ANY: &str = "*";
+
+/// Every canonical biome with its parent, if it has one. A monster living in a parent
+/// biome can turn up in any of its children and vice versa.
+const BIOMES: [(&str, Option<&str>); 20] = [
+    ("arctic", None),
+    ("desert", None),
+    ("forest", None),
+    ("grassland", None),
+    ("hills", None),
+    ("jungle", None),
+    ("mountain", None),
+    ("swamp", None),
+    ("rural", None),
+    ("urban", None),
+    ("ruins", None),
+    ("underground", None),
+    ("cave", Some("underground")),
+    ("deeps", Some("underground")),
+    ("tomb", Some("underground")),
+    ("dungeon", Some("underground")),
+    ("library", Some("dungeon")),
+    ("aquatic", None),
+    ("ocean", Some("aquatic")),
+    ("river/coast", Some("aquatic")),
+];
+
+const ALIASES: [(&str, &str); 9] = [
+    ("coastal", "river/coast"),
+    ("coast", "river/coast"),
+    ("river", "river/coast"),
+    ("sea", "ocean"),
+    ("caves", "cave"),
+    ("caverns", "cave"),
+    ("city", "urban"),
+    ("town", "urban"),
+    ("ruin", "ruins"),
+];
+
+/// The canonical name for a biome: trimmed, lower case and with aliases resolved.
+/// Names the registry doesn't know are returned trimmed and lower cased.
+pub fn normalize(name: &str) -> String {
+    let name = name.trim().to_lowercase();
+    match ALIASES.iter().find(|(alias, _)| *alias == name) {
+        Some((_, canonical)) => canonical.to_string(),
+        None => name,
+    }
+}
+
+pub fn is_known(name: &str) -> bool {
+    let name = normalize(name);
+    name == ANY || BIOMES.iter().any(|(biome, _)| *biome == name)
+}
+
+pub fn parent(name: &str) -> Option<&'static str> {
+    BIOMES
+        .iter()
+        .find(|(biome, _)| *biome == name)
+        .and_then(|(_, parent)| *parent)
+}
+
+/// The biome's parent, grandparent and so on.
+pub fn ancestors(name: &str) -> Vec<&'static str> {
+    let mut ancestors = Vec::new();
+    let mut current = parent(name);
+    while let Some(biome) = current {
+        ancestors.push(biome);
+        current = parent(biome);
+    }
+    ancestors
+}
+
+/// Whether two canonical biomes are the same place: equal, or one inside the other.
+pub fn related(a: &str, b: &str) -> bool {
+    a == b || is_inside(a, b) || is_inside(b, a)
+}
+
+/// Whether `name` sits somewhere inside `outer`. Walks the parents directly rather than
+/// collecting [`ancestors`], since this runs for every pair of monsters in the graph.
+fn is_inside(name: &str, outer: &str) -> bool {
+    let mut current = parent(name);
+    while let Some(biome) = current {
+        if biome == outer {
+            return true;
+        }
+        current = parent(biome);
+    }
+    false
+}
+
+/// Whether a monster found in `biomes` can be met in the `selected` biome.
+pub fn matches(biomes: &[String], selected: &str) -> bool {
+    biomes
+        .iter()
+        .any(|biome| biome == ANY || related(biome, selected))
+}
+
+/// The number of biomes two monsters share. Each biome on one side counts if the other
+/// side lives there too, and the smaller side's count is used, so `cave, deeps` against
+/// `underground` is 1 whichever way round. Either side living anywhere (`*`) shares
+/// every biome the other has.
+pub fn shared(a: &[String], b: &[String]) -> usize {
+    match (a.iter().any(|x| x == ANY), b.iter().any(|x| x == ANY)) {
+        (true, true) => a.len().max(b.len()),
+        (true, false) => b.len(),
+        (false, true) => a.len(),
+        (false, false) => {
+            let home = |biomes: &[String], other: &[String]| {
+                biomes.iter().filter(|biome| other.iter().any(|o| related(biome, o))).count()
+            };
+            home(a, b).min(home(b, a))
+        }
+    }
+}
+
+/// The biomes [`shared`] counts, from whichever side it counted: those of `a` that are
+/// also home to `b` unless fewer of `b`'s are home to `a`, or all of one side's when
+/// the other lives anywhere.
+pub fn common(a: &[String], b: &[String]) -> Vec<String> {
+    match (a.iter().any(|x| x == ANY), b.iter().any(|x| x == ANY)) {
+        (true, true) if b.len() > a.len() => b.to_vec(),
+        (true, true) => a.to_vec(),
+        (true, false) => b.to_vec(),
+        (false, true) => a.to_vec(),
+        (false, false) => {
+            let home = |biomes: &[String], other: &[String]| -> Vec<String> {
+                biomes.iter().filter(|biome| other.iter().any(|o| related(biome, o))).cloned().collect()
+            };
+            let (from_a, from_b) = (home(a, b), home(b, a));
+            if from_b.len() < from_a.len() {
+                from_b
+            } else {
+                from_a
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::biome;
+
+    #[test]
+    fn normalizes_names_and_aliases() {
+        assert_eq!(biome::normalize(" ruins"), "ruins");
+        assert_eq!(biome::normalize("Coastal"), "river/coast");
+        assert_eq!(biome::normalize("lava"), "lava");
+        assert!(biome::is_known("coastal"));
+        assert!(!biome::is_known("lava"));
+    }
+
+    #[test]
+    fn parents_include_children() {
+        let cave = vec!["cave".to_string()];
+        let library = vec!["library".to_string()];
+        assert!(biome::matches(&cave, "underground"));
+        assert!(biome::matches(&library, "underground"));
+        assert!(biome::matches(&["underground".to_string()], "cave"));
+        assert!(!biome::matches(&cave, "deeps"));
+        assert!(biome::matches(&["*".to_string()], "deeps"));
+    }
+
+    #[test]
+    fn shared_biomes() {
+        let a = vec!["cave".to_string(), "forest".to_string()];
+        let b = vec!["underground".to_string(), "swamp".to_string()];
+        assert_eq!(biome::shared(&a, &b), 1);
+        assert_eq!(biome::shared(&a, &["*".to_string()]), 2);
+        assert_eq!(biome::common(&a, &b), vec!["cave"]);
+
+        let strings = |names: &[&str]| names.iter().map(|n| n.to_string()).collect::<Vec<_>>();
+        let pairs = [
+            (strings(&["cave", "deeps"]), strings(&["underground"])),
+            (strings(&["cave", "forest"]), strings(&["underground", "swamp"])),
+            (strings(&["*", "forest"]), strings(&["*"])),
+            (strings(&["library", "tomb"]), strings(&["dungeon", "underground"])),
+        ];
+        for (a, b) in &pairs {
+            assert_eq!(biome::shared(a, b), biome::shared(b, a), "{:?} and {:?}", a, b);
+            assert_eq!(biome::common(a, b).len(), biome::shared(a, b));
+        }
+        assert_eq!(biome::shared(&pairs[0].0, &pairs[0].1), 1);
+    }
+}
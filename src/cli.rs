@@ -0,0 +1,711 @@
+use randommon::data::{Monster, OutputFormat};
+use randommon::dice::Dice;
+use randommon::encounter::{Difficulty, Encounter, Party};
+use randommon::export::GraphFormat;
+use randommon::filter::{self, ValueFilter};
+use randommon::monster_loader::{self, LoadError};
+use randommon::scoring::{self, ScoringProfile};
+use randommon::sources;
+use randommon::table::{self, EncounterTable, TableFormat};
+use randommon::walk::WalkStrategy;
+use randommon::monster_wrangler::{self, Choices, MonsterWrangler, PickError};
+use std::ops::RangeInclusive;
+use std::path::PathBuf;
+
+pub const USAGE: &str = "Usage: randommon [COMMAND] [OPTIONS] [PACK...]
+
+With no command, starts the interactive menu.
+
+Commands:
+  list               Every monster matching the filters
+  search <term>      Monsters whose name, tags or biomes contain <term>
+  random             One random monster
+  cluster            A seed monster and its closest relatives
+  walk               A chain of related monsters starting from a seed
+  encounter          A leader and followers sized for the party and difficulty
+  wandering          A wandering monster group with its activity, distance,
+                     surprise, reaction and morale
+  table <dice> [fmt] A random encounter table for d6, d8, 2d6... as markdown
+                     (default), html or json
+  show <name>        The monster with exactly this name
+  explain <a> <b>    What monsters a and b have in common and how much it counts
+  links <name>       The --count strongest and weakest links from this monster
+  path <a> <b>       The chain of strongest links leading from monster a to b
+  factions           The --count largest factions of related monsters
+  export <format>    The graph of matching monsters as dot, graphml or json
+  sources            Every source with its title and number of monsters
+  lint               Check the monster packs for problems
+
+Options:
+  --level <n|n-m>       Only monsters of this level, or in this range of levels
+  --biome <biomes>      Only monsters found in these biomes
+  --tag <tags>          Only monsters with these tags
+  --source <sources>    Only monsters from these sources
+  --alignment <L|N|C>   Only monsters of these alignments
+  --move <modes>        Only monsters with these movement modes (fly, swim...)
+  --rarity <rarities>   Only monsters of these rarities (common, uncommon, rare,
+                        unique); -unique rules out named individuals
+  --randomness <1-5>    How far from the seed a cluster may reach (default 1)
+  --count <n>           How many monsters to add to a cluster or walk (default 5)
+  --numbers             Roll how many of each monster in a cluster or walk turn up
+  --roll-hp             Roll hit points for each monster in a cluster, walk,
+                        encounter, wandering group or table roll
+  --roll                Roll once on the table instead of printing it
+  --walk <strategy>     How a walk picks each step: skip (default), weighted,
+                        softmax[:temperature], ramp, biome or new-tags
+  --seed-monster <name> Start a cluster, walk, encounter or wandering group from
+                        this monster
+  --party-size <n>      How many characters an encounter is for (default 4)
+  --party-level <n>     The characters' average level (default 1)
+  --difficulty <name>   easy, normal, hard or deadly (default normal)
+  --seed <n>            Repeat the random picks of an earlier run
+  --min-strength <n>    Only export links at least this strong (default 1)
+  --profile <name>      Score relatives with this profile (default, ecological,
+                        thematic, same-book or one from profiles.json)
+  --format <format>     standard, name or html (default standard)
+  --data-dir <dir>      Load user packs from this directory
+  --pack <file>         Load this user pack (may be repeated)
+  -h, --help            Show this message
+
+Biomes, tags, sources, alignments, movement modes and rarities are lists:
+forest,swamp matches either, undead+fey needs both and -dragon rules
+dragons out.";
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+    Interactive,
+    Lint,
+    List,
+    Search(String),
+    Random,
+    Cluster,
+    Walk,
+    Encounter,
+    Wandering,
+    Table(Dice, TableFormat),
+    Show(String),
+    Explain(String, String),
+    Links(String),
+    Path(String, String),
+    Factions,
+    Export(GraphFormat),
+    Sources,
+    Help,
+}
+
+#[derive(Debug, Clone)]
+pub struct Options {
+    pub command: Command,
+    pub data_dir: Option<PathBuf>,
+    pub packs: Vec<PathBuf>,
+    pub levels: Option<RangeInclusive<u8>>,
+    pub biomes: ValueFilter,
+    pub tags: ValueFilter,
+    pub sources: ValueFilter,
+    pub alignments: ValueFilter,
+    pub movement: ValueFilter,
+    pub rarities: ValueFilter,
+    pub randomness: u8,
+    pub walk: WalkStrategy,
+    pub numbers: bool,
+    pub roll_hp: bool,
+    pub roll: bool,
+    pub count: i32,
+    pub party: Party,
+    pub difficulty: Difficulty,
+    pub seed_monster: Option<String>,
+    pub seed: Option<u64>,
+    pub profile: Option<String>,
+    pub format: OutputFormat,
+    pub min_strength: i32,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            command: Command::Interactive,
+            data_dir: None,
+            packs: Vec::new(),
+            levels: None,
+            biomes: ValueFilter::default(),
+            tags: ValueFilter::default(),
+            sources: ValueFilter::default(),
+            alignments: ValueFilter::default(),
+            movement: ValueFilter::default(),
+            rarities: ValueFilter::default(),
+            randomness: 1,
+            walk: WalkStrategy::Skip,
+            numbers: false,
+            roll_hp: false,
+            roll: false,
+            count: 5,
+            party: Party::default(),
+            difficulty: Difficulty::Normal,
+            seed_monster: None,
+            seed: None,
+            profile: None,
+            format: OutputFormat::Standard,
+            min_strength: 1,
+        }
+    }
+}
+
+pub fn parse(args: &[String]) -> Result<Options, String> {
+    let mut options = Options::default();
+    let mut args = args.iter();
+    let mut positional = Vec::new();
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| {
+            args.next()
+                .cloned()
+                .ok_or_else(|| format!("{} requires a value", name))
+        };
+        match arg.as_str() {
+            "-h" | "--help" => options.command = Command::Help,
+            "--numbers" => options.numbers = true,
+            "--roll-hp" => options.roll_hp = true,
+            "--roll" => options.roll = true,
+            "--level" => options.levels = Some(filter::parse_levels(&value(arg)?)?),
+            "--biome" => options.biomes = ValueFilter::parse(&value(arg)?)?,
+            "--tag" => options.tags = ValueFilter::parse(&value(arg)?)?,
+            "--source" => options.sources = ValueFilter::parse(&value(arg)?)?,
+            "--alignment" => options.alignments = ValueFilter::parse(&value(arg)?)?,
+            "--move" => options.movement = ValueFilter::parse(&value(arg)?)?,
+            "--rarity" => options.rarities = ValueFilter::parse(&value(arg)?)?,
+            "--randomness" => {
+                options.randomness = parse_number(arg, &value(arg)?)?;
+                if !(1..=5).contains(&options.randomness) {
+                    return Err("--randomness must be between 1 and 5".to_string());
+                }
+            }
+            "--count" => options.count = parse_number(arg, &value(arg)?)?,
+            "--walk" => options.walk = WalkStrategy::parse(&value(arg)?)?,
+            "--party-size" => options.party.size = parse_number(arg, &value(arg)?)?,
+            "--party-level" => options.party.level = parse_number(arg, &value(arg)?)?,
+            "--difficulty" => options.difficulty = Difficulty::parse(&value(arg)?)?,
+            "--seed-monster" => options.seed_monster = Some(value(arg)?),
+            "--seed" => options.seed = Some(parse_number(arg, &value(arg)?)?),
+            "--profile" => options.profile = Some(value(arg)?),
+            "--format" => options.format = parse_format(&value(arg)?)?,
+            "--min-strength" => options.min_strength = parse_number(arg, &value(arg)?)?,
+            "--data-dir" => options.data_dir = Some(PathBuf::from(value(arg)?)),
+            "--pack" => options.packs.push(PathBuf::from(value(arg)?)),
+            _ if arg.starts_with("--") => return Err(format!("Unknown option {}", arg)),
+            _ => positional.push(arg.clone()),
+        }
+    }
+
+    if options.command == Command::Help {
+        return Ok(options);
+    }
+    let mut positional = positional.into_iter().peekable();
+    let command = match positional.peek().map(|c| c.as_str()) {
+        Some("list") => Some(Command::List),
+        Some("search") => Some(Command::Search(String::new())),
+        Some("random") => Some(Command::Random),
+        Some("cluster") => Some(Command::Cluster),
+        Some("walk") => Some(Command::Walk),
+        Some("encounter") => Some(Command::Encounter),
+        Some("wandering") => Some(Command::Wandering),
+        Some("table") => Some(Command::Table(Dice::new(1, 6), TableFormat::Markdown)),
+        Some("show") => Some(Command::Show(String::new())),
+        Some("explain") => Some(Command::Explain(String::new(), String::new())),
+        Some("links") => Some(Command::Links(String::new())),
+        Some("path") => Some(Command::Path(String::new(), String::new())),
+        Some("factions") => Some(Command::Factions),
+        Some("export") => Some(Command::Export(GraphFormat::Json)),
+        Some("sources") => Some(Command::Sources),
+        Some("lint") => Some(Command::Lint),
+        _ => None,
+    };
+    if let Some(command) = command {
+        positional.next();
+        options.command = match command {
+            Command::Search(_) => Command::Search(positional.next().ok_or("search requires a search term")?),
+            Command::Show(_) => Command::Show(positional.next().ok_or("show requires a monster name")?),
+            Command::Explain(_, _) => match (positional.next(), positional.next()) {
+                (Some(from), Some(to)) => Command::Explain(from, to),
+                _ => return Err("explain requires two monster names".to_string()),
+            },
+            Command::Links(_) => Command::Links(positional.next().ok_or("links requires a monster name")?),
+            Command::Export(_) => {
+                Command::Export(GraphFormat::parse(&positional.next().ok_or("export requires a format")?)?)
+            }
+            Command::Table(_, _) => {
+                let dice = table::parse_dice(&positional.next().ok_or("table requires dice like d8 or 2d6")?)?;
+                match positional.next() {
+                    Some(format) => Command::Table(dice, TableFormat::parse(&format)?),
+                    None => Command::Table(dice, TableFormat::Markdown),
+                }
+            }
+            Command::Path(_, _) => match (positional.next(), positional.next()) {
+                (Some(from), Some(to)) => Command::Path(from, to),
+                _ => return Err("path requires two monster names".to_string()),
+            },
+            command => command,
+        };
+    }
+    // Anything left over is a pack file, which only makes sense where there are no other arguments.
+    for arg in positional {
+        if !matches!(options.command, Command::Interactive | Command::Lint) {
+            return Err(format!("Unexpected argument {}", arg));
+        }
+        options.packs.push(PathBuf::from(arg));
+    }
+    Ok(options)
+}
+
+fn parse_number<T: std::str::FromStr>(option: &str, value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("{} must be a number, not '{}'", option, value))
+}
+
+fn parse_format(value: &str) -> Result<OutputFormat, String> {
+    match value.to_lowercase().as_str() {
+        "standard" => Ok(OutputFormat::Standard),
+        "name" => Ok(OutputFormat::Name),
+        "html" => Ok(OutputFormat::Html),
+        _ => Err(format!("Unknown output format '{}'", value)),
+    }
+}
+
+impl Options {
+    /// Rolled hit points for every monster in the groups, or none without `--roll-hp`.
+    fn hit_points(&self, choices: &Choices, groups: &[(Monster, u32)]) -> Vec<Vec<u16>> {
+        if self.roll_hp {
+            choices.roll_hit_points(groups)
+        } else {
+            Vec::new()
+        }
+    }
+
+    /// User packs come from the data directory (`--data-dir`, falling back to
+    /// `monster_loader::default_data_dir`) followed by any packs named on the command line.
+    pub fn user_packs(&self) -> Vec<PathBuf> {
+        let data_dir = self.data_dir();
+        let mut packs = match data_dir {
+            Some(dir) => monster_loader::discover_packs(&dir),
+            None => Vec::new(),
+        };
+        packs.extend(self.packs.iter().cloned());
+        packs
+    }
+
+    /// The built-in scoring profiles and any in the data directory's `profiles.json`.
+    pub fn profiles(&self) -> Result<Vec<ScoringProfile>, LoadError> {
+        match self.data_dir() {
+            Some(dir) => scoring::load_profiles(&dir.join(scoring::PROFILES_FILE)),
+            None => Ok(scoring::built_in_profiles()),
+        }
+    }
+
+    fn data_dir(&self) -> Option<PathBuf> {
+        self.data_dir.clone().or_else(monster_loader::default_data_dir)
+    }
+
+    fn choices(&self, wrangler: &MonsterWrangler) -> Result<Choices, String> {
+        let mut choices = wrangler
+            .choices()
+            .with_levels(self.levels.clone())
+            .with_biomes(self.biomes.clone())
+            .with_tags(self.tags.clone())
+            .with_sources(self.sources.clone())
+            .with_alignments(self.alignments.clone())
+            .with_movement(self.movement.clone())
+            .with_rarities(self.rarities.clone())
+            .with_randomness(Some(self.randomness))
+            .with_walk(self.walk)
+            .with_numbers(self.numbers)
+            .with_seed(Some(self.seed.unwrap_or_else(monster_wrangler::random_seed)))
+            .with_profile(self.profile.clone());
+        if let Some(name) = &self.seed_monster {
+            let monster = wrangler
+                .find(name)
+                .ok_or_else(|| format!("No monster named '{}'", name))?;
+            choices = choices.with_seed_monster(Some(monster));
+        }
+        Ok(choices)
+    }
+}
+
+/// Runs a non-interactive command, printing results (and nothing else) to stdout. The
+/// seed behind any random picks goes to stderr so the run can be repeated with `--seed`.
+/// Returns the process exit code: 0 on success, 1 when nothing matched.
+pub fn run(options: &Options, wrangler: &MonsterWrangler) -> i32 {
+    if options.command == Command::Sources {
+        print_sources(wrangler);
+        return 0;
+    }
+    let choices = match options.choices(wrangler) {
+        Ok(choices) => choices,
+        Err(e) => {
+            eprintln!("{}", e);
+            return 1;
+        }
+    };
+    match &options.command {
+        Command::Explain(from, to) => return explain(wrangler, &choices, from, to),
+        Command::Links(name) => return links(wrangler, &choices, name, options.count),
+        Command::Encounter => {
+            return match choices.encounter(options.party, options.difficulty, wrangler) {
+                Ok(encounter) => {
+                    eprintln!("Seed: {}", choices.seed().unwrap());
+                    let hit_points = options.hit_points(&choices, &encounter.groups);
+                    print_encounter(&encounter, &hit_points, options.format);
+                    0
+                }
+                Err(e) => {
+                    eprintln!("{}", e);
+                    1
+                }
+            }
+        }
+        Command::Wandering => {
+            return match choices.wandering(wrangler) {
+                Ok(wandering) => {
+                    eprintln!("Seed: {}", choices.seed().unwrap());
+                    println!("{}", wandering);
+                    let groups = [(wandering.monster, wandering.count)];
+                    print_groups(&groups, &options.hit_points(&choices, &groups), options.format);
+                    0
+                }
+                Err(e) => {
+                    eprintln!("{}", e);
+                    1
+                }
+            }
+        }
+        Command::Table(dice, format) => {
+            return match choices.table(*dice, wrangler) {
+                Ok(table) => {
+                    eprintln!("Seed: {}", choices.seed().unwrap());
+                    if options.roll {
+                        roll(&table, options, &choices);
+                    } else {
+                        print!("{}", table.render(*format));
+                    }
+                    0
+                }
+                Err(e) => {
+                    eprintln!("{}", e);
+                    1
+                }
+            }
+        }
+        Command::Factions => return factions(wrangler, &choices, options.count, options.format),
+        Command::Export(format) => {
+            return match wrangler.export(&choices, options.min_strength, *format) {
+                Ok(graph) => {
+                    print!("{}", graph);
+                    0
+                }
+                Err(e) => {
+                    eprintln!("{}", e);
+                    1
+                }
+            }
+        }
+        _ => {}
+    }
+    let monsters = match &options.command {
+        Command::List => wrangler.list(&choices),
+        Command::Search(term) => wrangler.search(&choices, term),
+        Command::Random => wrangler.rando(&choices).map(|monster| vec![monster]).unwrap_or_else(report),
+        Command::Cluster => choices.cluster(options.count, wrangler).unwrap_or_else(report),
+        Command::Walk => choices.walk(options.count, wrangler).unwrap_or_else(report),
+        Command::Show(name) => match find(wrangler, name) {
+            Some(monster) => vec![monster],
+            None => return 1,
+        },
+        Command::Path(from, to) => match (find(wrangler, from), find(wrangler, to)) {
+            (Some(from), Some(to)) => wrangler.path(&choices, &from, &to).unwrap_or_else(report),
+            _ => return 1,
+        },
+        Command::Interactive
+        | Command::Lint
+        | Command::Explain(_, _)
+        | Command::Links(_)
+        | Command::Factions
+        | Command::Encounter
+        | Command::Wandering
+        | Command::Table(_, _)
+        | Command::Export(_)
+        | Command::Sources
+        | Command::Help => Vec::new(),
+    };
+
+    if monsters.is_empty() {
+        // Errors from random picks have already been reported.
+        if matches!(options.command, Command::List | Command::Search(_)) {
+            if wrangler.list(&choices).is_empty() {
+                eprintln!("{}", wrangler.no_matches(&choices));
+            } else {
+                eprintln!("No monsters match {} and the search term", choices.state());
+            }
+        }
+        return 1;
+    }
+    if matches!(options.command, Command::Random | Command::Cluster | Command::Walk) {
+        eprintln!("Seed: {}", choices.seed().unwrap());
+    }
+    if matches!(options.command, Command::Cluster | Command::Walk) {
+        let groups = choices.counted(monsters);
+        print_groups(&groups, &options.hit_points(&choices, &groups), options.format);
+    } else {
+        print_monsters(&monsters, options.format);
+    }
+    0
+}
+
+/// The monster with this name, or `None` after suggesting similar names on stderr.
+fn find(wrangler: &MonsterWrangler, name: &str) -> Option<Monster> {
+    let monster = wrangler.find(name);
+    if monster.is_none() {
+        eprintln!("No monster named '{}'", name);
+        let similar = wrangler.search(&wrangler.choices(), name);
+        if !similar.is_empty() {
+            eprintln!("Did you mean:");
+            for monster in similar {
+                eprintln!("  {}", monster.name);
+            }
+        }
+    }
+    monster
+}
+
+fn explain(wrangler: &MonsterWrangler, choices: &Choices, from: &str, to: &str) -> i32 {
+    let (Some(from), Some(to)) = (find(wrangler, from), find(wrangler, to)) else {
+        return 1;
+    };
+    match wrangler.explain(choices, &from, &to) {
+        Ok(connection) => {
+            println!("{}", connection);
+            0
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            1
+        }
+    }
+}
+
+/// Prints the strongest links, then the weakest, among monsters matching the filters.
+fn links(wrangler: &MonsterWrangler, choices: &Choices, name: &str, count: i32) -> i32 {
+    let Some(monster) = find(wrangler, name) else {
+        return 1;
+    };
+    let (strongest, weakest) = match wrangler.links(choices, &monster, count.max(0) as usize) {
+        Ok(links) => links,
+        Err(e) => {
+            eprintln!("{}", e);
+            return 1;
+        }
+    };
+    if strongest.is_empty() {
+        eprintln!("{} has no relatives matching {}", monster.name, choices.state());
+        return 1;
+    }
+    println!("Strongest links:");
+    for connection in &strongest {
+        println!("{}", connection);
+    }
+    println!("Weakest links:");
+    for connection in &weakest {
+        println!("{}", connection);
+    }
+    0
+}
+
+/// Prints each faction's name followed by its monsters.
+fn factions(wrangler: &MonsterWrangler, choices: &Choices, count: i32, format: OutputFormat) -> i32 {
+    match wrangler.factions(choices, count.max(0) as usize) {
+        Ok(factions) => {
+            for faction in factions {
+                println!("== {}", faction);
+                print_monsters(&faction.monsters, format);
+            }
+            0
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            1
+        }
+    }
+}
+
+/// Prints why no monsters could be picked, leaving nothing to print to stdout.
+fn report(error: PickError) -> Vec<Monster> {
+    eprintln!("{}", error);
+    Vec::new()
+}
+
+/// One tab-separated line per source in source order: code, title, monster count and
+/// whether the source is left out unless asked for.
+fn print_sources(wrangler: &MonsterWrangler) {
+    let all = Choices::default();
+    for code in all.sources(wrangler) {
+        let count = wrangler.list(&all.with_source(code.clone())).len();
+        let enabled = sources::enabled(&code);
+        println!(
+            "{}\t{}\t{}{}",
+            code,
+            sources::title(&code),
+            count,
+            if enabled { "" } else { "\tdisabled" }
+        );
+    }
+}
+
+/// The encounter's heading, then each kind of monster with how many there are.
+fn print_encounter(encounter: &Encounter, hit_points: &[Vec<u16>], format: OutputFormat) {
+    println!("{}", encounter);
+    print_groups(&encounter.groups, hit_points, format);
+}
+
+/// Rolls on the table, printing the result and what it leads to.
+fn roll(table: &EncounterTable, options: &Options, choices: &Choices) {
+    let (result, entry, count) = choices.roll_table(table);
+    println!("Rolled {} on {}", result, table.dice);
+    let groups = [(entry.monster.clone(), count)];
+    print_groups(&groups, &options.hit_points(choices, &groups), options.format);
+}
+
+/// Each kind of monster with how many there are and, if any were rolled, the hit points
+/// of each one.
+fn print_groups(groups: &[(Monster, u32)], hit_points: &[Vec<u16>], format: OutputFormat) {
+    for (i, (monster, count)) in groups.iter().enumerate() {
+        let hit_points = hit_points.get(i).map_or(&[][..], |hp| hp.as_slice());
+        println!("{}", monster.render_group(*count, hit_points, format));
+    }
+}
+
+fn print_monsters(monsters: &[Monster], format: OutputFormat) {
+    for monster in monsters {
+        println!("{}", monster.render(format));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cli::{parse, Command};
+    use randommon::data::{Monsters, OutputFormat};
+    use randommon::encounter::{Difficulty, Party};
+    use randommon::dice::Dice;
+    use randommon::export::GraphFormat;
+    use randommon::monster_loader::{self, Pack};
+    use randommon::monster_wrangler::MonsterWrangler;
+    use randommon::table::TableFormat;
+    use randommon::walk::WalkStrategy;
+
+    fn args(line: &str) -> Vec<String> {
+        line.split_whitespace().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn source_exclusions_keep_disabled_sources_out() {
+        let entry = |name: &str, source: &str| {
+            format!(
+                r#"{{"name": "{}", "tags": "humanoid", "level": "1", "biome": "cave", "alignment": "C", "move": "", "attack": "", "page": "1", "source": "{}",
+                "statblock": "AC 11, HP 5, ATK 1 club +0 (1d4), MV near, S +0, D +1, C +1, I -1, W -1, Ch -2, AL C, LV 1"}}"#,
+                name, source
+            )
+        };
+        let pack = Pack {
+            file: "test.json".to_string(),
+            contents: format!("[{},{},{}]", entry("GOBLIN", "core"), entry("BANDIT", "cs1"), entry("LIBRARIAN", "stygian library")),
+        };
+        let wrangler = MonsterWrangler::new(Monsters::new(monster_loader::load_packs(&[pack]).unwrap()));
+        let names = |line: &str| {
+            let choices = parse(&args(line)).unwrap().choices(&wrangler).unwrap();
+            wrangler.list(&choices).into_iter().map(|m| m.name).collect::<Vec<_>>()
+        };
+        assert_eq!(names("list"), vec!["GOBLIN", "BANDIT"]);
+        assert_eq!(names("list --source -cs1"), vec!["GOBLIN"]);
+        assert_eq!(names("list --source core,stygian"), vec!["GOBLIN"]);
+    }
+
+    #[test]
+    fn subcommands_and_flags() {
+        let options = parse(&args("cluster --level 3-5 --biome forest,swamp --count 2 --format name")).unwrap();
+        assert_eq!(options.command, Command::Cluster);
+        assert_eq!(options.levels, Some(3..=5));
+        assert_eq!(options.biomes.values, vec!["forest", "swamp"]);
+        assert_eq!(options.count, 2);
+        assert_eq!(options.format, OutputFormat::Name);
+
+        let options = parse(&args("list --source core,-cs1 --alignment L --move fly+swim")).unwrap();
+        assert_eq!(options.sources.excluded, vec!["cs1"]);
+        assert_eq!(options.alignments.values, vec!["L"]);
+        assert_eq!(options.movement.values, vec!["fly", "swim"]);
+
+        let options = parse(&args("random --rarity -unique")).unwrap();
+        assert_eq!(options.rarities.excluded, vec!["unique"]);
+
+        let options = parse(&args("explain goblin bandit --profile thematic")).unwrap();
+        assert_eq!(options.command, Command::Explain("goblin".to_string(), "bandit".to_string()));
+
+        let options = parse(&args("encounter --party-size 5 --party-level 3 --difficulty deadly --roll-hp")).unwrap();
+        assert_eq!(options.command, Command::Encounter);
+        assert!(options.roll_hp);
+        assert_eq!(options.party, Party { size: 5, level: 3 });
+        assert_eq!(options.difficulty, Difficulty::Deadly);
+
+        let options = parse(&args("factions --biome swamp --count 3")).unwrap();
+        assert_eq!(options.command, Command::Factions);
+        assert_eq!(options.count, 3);
+
+        let options = parse(&args("export graphml --min-strength 30 --tag undead")).unwrap();
+        assert_eq!(options.command, Command::Export(GraphFormat::GraphMl));
+        assert_eq!(options.min_strength, 30);
+
+        let options = parse(&args("wandering --biome swamp --roll-hp")).unwrap();
+        assert_eq!(options.command, Command::Wandering);
+
+        let options = parse(&args("table 2d6 html --biome cave")).unwrap();
+        assert_eq!(options.command, Command::Table(Dice::new(2, 6), TableFormat::Html));
+        let options = parse(&args("table d8 --roll")).unwrap();
+        assert_eq!(options.command, Command::Table(Dice::new(1, 8), TableFormat::Markdown));
+        assert!(options.roll);
+
+        let options = parse(&args("path goblin vampire --level 1-10")).unwrap();
+        assert_eq!(options.command, Command::Path("goblin".to_string(), "vampire".to_string()));
+
+        let options = parse(&args("walk --seed 42 --profile thematic --walk softmax:5")).unwrap();
+        assert_eq!(options.command, Command::Walk);
+        assert_eq!(options.walk, WalkStrategy::Softmax { temperature: 5.0 });
+        assert_eq!(options.seed, Some(42));
+        assert_eq!(options.profile.as_deref(), Some("thematic"));
+
+        let options = parse(&args("search --tag undead,-dragon ghoul")).unwrap();
+        assert_eq!(options.command, Command::Search("ghoul".to_string()));
+        assert_eq!(options.tags.values, vec!["undead"]);
+        assert_eq!(options.tags.excluded, vec!["dragon"]);
+    }
+
+    #[test]
+    fn packs_without_a_command() {
+        let options = parse(&args("--data-dir zines extra.json")).unwrap();
+        assert_eq!(options.command, Command::Interactive);
+        assert_eq!(options.packs.len(), 1);
+    }
+
+    #[test]
+    fn usage_errors() {
+        assert!(parse(&args("show")).is_err());
+        assert!(parse(&args("explain goblin")).is_err());
+        assert!(parse(&args("path goblin")).is_err());
+        assert!(parse(&args("export")).is_err());
+        assert!(parse(&args("export svg")).is_err());
+        assert!(parse(&args("table")).is_err());
+        assert!(parse(&args("table d1")).is_err());
+        assert!(parse(&args("table d8 pdf")).is_err());
+        assert!(parse(&args("walk --walk sideways")).is_err());
+        assert!(parse(&args("encounter --difficulty brutal")).is_err());
+        assert!(parse(&args("list --level three")).is_err());
+        assert!(parse(&args("list --level 5-3")).is_err());
+        assert!(parse(&args("list --randomness 9")).is_err());
+        assert!(parse(&args("list extra.json")).is_err());
+        assert!(parse(&args("list --frobnicate")).is_err());
+    }
+}
@@ -1,14 +1,26 @@
+use crate::biome;
+use crate::dice::{self, Dice};
+use crate::rarity::Rarity;
+use crate::scoring::ScoringProfile;
+use crate::sources;
+use crate::text::TextIndex;
+use crate::stat_block::StatBlock;
+use rand::Rng;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, HashSet};
 use std::fmt;
-use std::hash::Hash;
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
 
+/// An ability exactly as it appears in a monster pack.
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct RawAbility {
     pub name: String,
     pub description: String,
 }
 
+/// A monster exactly as it appears in a monster pack's JSON.
 #[derive(Serialize, Deserialize, Debug)]
 pub struct RawMonster {
     pub name: String,
@@ -25,6 +37,11 @@ pub struct RawMonster {
     pub source: String,
     pub description: Option<String>,
     pub abilities: Option<Vec<RawAbility>>,
+    /// How many turn up together, as dice like `2d4`. Guessed from the level and tags
+    /// when missing.
+    pub number_appearing: Option<String>,
+    /// `common`, `uncommon`, `rare` or `unique`. Guessed from the tags when missing.
+    pub rarity: Option<String>,
 }
 
 #[derive(Debug, Clone, Hash)]
@@ -33,12 +50,15 @@ pub struct Ability {
     pub description: String,
 }
 
-#[derive(Debug, Clone, Hash)]
+/// A monster ready for filtering and rendering, built from a [`RawMonster`].
+#[derive(Debug, Clone)]
 pub struct Monster {
+    /// Unique within one load, in the order the monsters were loaded.
     pub id: MonsterId,
     pub name: String,
     pub tags: Vec<String>,
     pub level: u8,
+    /// Canonical biome names, see [`crate::biome`].
     pub biomes: Vec<String>,
     pub alignment: String,
     pub move_amount: String,
@@ -46,54 +66,40 @@ pub struct Monster {
     pub page: String,
     pub raw_stat_block: String,
     pub source: String,
+    /// The pack file this monster was loaded from.
+    pub file: String,
     pub stat_block: StatBlock,
     pub description: Option<String>,
     pub abilities: Option<Vec<Ability>>,
+    pub number_appearing: Dice,
+    pub rarity: Rarity,
 }
 
-#[derive(Debug, Clone, Hash)]
-pub struct StatBlock {
-    move_amount: String,
-    attack: String,
-    ac: String,
-    hp: String,
-    stats: String,
-}
-
+/// How [`Monster::render`] lays a monster out.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum OutputFormat {
+    /// The full stat block, description and abilities on tab-indented lines.
     Standard,
+    /// Just the name.
     Name,
+    /// The full stat block as a single HTML fragment.
     Html,
 }
 
-impl StatBlock {
-    pub(crate) fn parse(full: &String) -> StatBlock {
-        let pieces: Vec<&str> = full.split(",").collect();
-
-        StatBlock {
-            ac: pieces[0].to_string(),
-            hp: pieces[1].to_string(),
-            attack: pieces[2].trim().to_string(),
-            move_amount: pieces[3].to_string(),
-            stats: pieces[4..10].join(",").trim().to_string(),
-        }
-    }
-}
-
 impl Monster {
+    /// The [`OutputFormat::Standard`] rendering.
     pub fn detailed_summary(&self) -> String {
         let mut out = format!(
             "{} [ref: {}]\n\t{}\t{}\t{}\tLV:{}\tAL:{}\n\t{}\n\t{}",
             self.name,
-            self.page,
-            self.stat_block.ac,
-            self.stat_block.hp,
-            self.stat_block.move_amount,
+            self.reference(),
+            self.stat_block.ac_text(),
+            self.stat_block.hp_text(),
+            self.stat_block.movement_text(),
             self.level,
             self.alignment,
-            self.stat_block.attack,
-            self.stat_block.stats
+            self.stat_block.attack_text(),
+            self.stat_block.stats_text()
         );
         if let Some(desc) = &self.description {
             out.push_str(&format!("\n\t{}", desc));
@@ -106,16 +112,17 @@ impl Monster {
         out
     }
 
+    /// The [`OutputFormat::Html`] rendering.
     pub fn html_summary(&self) -> String {
         let mut out = format!(
-            "<b>{}</b> (ref: {})<br>{},{}, {},{}, {}, AL {}, LV {}",
+            "<b>{}</b> (ref: {})<br>{}, {}, {}, {}, {}, AL {}, LV {}",
             self.name,
-            self.page,
-            self.stat_block.ac,
-            self.stat_block.hp,
-            self.stat_block.attack,
-            self.stat_block.move_amount,
-            self.stat_block.stats,
+            self.reference(),
+            self.stat_block.ac_text(),
+            self.stat_block.hp_text(),
+            self.stat_block.attack_text(),
+            self.stat_block.movement_text(),
+            self.stat_block.stats_text(),
             self.alignment,
             self.level,
         );
@@ -130,6 +137,11 @@ impl Monster {
         out
     }
 
+    /// Where to find the monster, e.g. `Cursed Scroll 1 p. 46`.
+    pub fn reference(&self) -> String {
+        sources::reference(&self.source, &self.page)
+    }
+
     pub fn render(&self, format: OutputFormat) -> String {
         match format {
             OutputFormat::Standard => self.detailed_summary(),
@@ -137,6 +149,36 @@ impl Monster {
             OutputFormat::Html => self.html_summary(),
         }
     }
+
+    /// The rendering for `count` of this monster, e.g. `3× GOBLIN`. A single monster is
+    /// rendered as it is.
+    pub fn render_count(&self, count: u32, format: OutputFormat) -> String {
+        let rendered = self.render(format);
+        match format {
+            _ if count == 1 => rendered,
+            OutputFormat::Html => rendered.replacen("<b>", &format!("<b>{}× ", count), 1),
+            OutputFormat::Standard | OutputFormat::Name => format!("{}× {}", count, rendered),
+        }
+    }
+
+    /// [`Monster::render_count`] followed by each individual's rolled hit points.
+    pub fn render_group(&self, count: u32, hit_points: &[u16], format: OutputFormat) -> String {
+        let rendered = self.render_count(count, format);
+        if hit_points.is_empty() {
+            return rendered;
+        }
+        let hit_points: Vec<String> = hit_points.iter().map(|hp| hp.to_string()).collect();
+        match format {
+            OutputFormat::Standard => format!("{}\n\tHP rolled: {}", rendered, hit_points.join(", ")),
+            OutputFormat::Name => format!("{} (HP {})", rendered, hit_points.join(", ")),
+            OutputFormat::Html => format!("{}<br>HP rolled: {}", rendered, hit_points.join(", ")),
+        }
+    }
+
+    /// Fresh hit points for one of this monster, from its level and constitution.
+    pub fn roll_hit_points(&self, rng: &mut impl Rng) -> u16 {
+        dice::roll_hit_points(self.level, self.stat_block.abilities.constitution, rng)
+    }
 }
 
 impl PartialEq for Monster {
@@ -145,8 +187,16 @@ impl PartialEq for Monster {
     }
 }
 
+impl Hash for Monster {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.id.hash(state);
+    }
+}
+
 pub type MonsterId = u32;
 
+/// How strongly two monsters are related, broken down by what they have in common.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
 pub struct Strength {
     pub total: i32,
     pub level: i32,
@@ -154,29 +204,112 @@ pub struct Strength {
     pub biome: i32,
     pub alignment: i32,
     pub source: i32,
+    pub text: i32,
 }
 
 impl fmt::Display for Strength {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "strength: {} (lvl: {} tag: {} bio: {} al: {} src: {})", self.total, self.level, self.tag, self.biome, self.alignment, self.source)
+        write!(f, "strength: {} (lvl: {} tag: {} bio: {} al: {} src: {} txt: {})", self.total, self.level, self.tag, self.biome, self.alignment, self.source, self.text)
     }
 }
 
+/// Everything two monsters have in common and what each part adds to their [`Strength`].
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Connection {
+    pub from: String,
+    pub to: String,
+    pub level_gap: u8,
+    pub shared_tags: Vec<String>,
+    pub shared_biomes: Vec<String>,
+    pub alignments: (String, String),
+    pub sources: (String, String),
+    /// How alike their descriptions and abilities are, from 0 to 1.
+    pub text_similarity: f32,
+    /// The words that do the most to make their text alike.
+    pub shared_terms: Vec<String>,
+    pub strength: Strength,
+}
+
+impl fmt::Display for Connection {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let list = |items: &[String]| if items.is_empty() { "none".to_string() } else { items.join(", ") };
+        writeln!(f, "{} -> {}: strength {}", self.from, self.to, self.strength.total)?;
+        writeln!(f, "\tlevel: {:+} ({} apart)", self.strength.level, self.level_gap)?;
+        writeln!(f, "\ttags: {:+} ({})", self.strength.tag, list(&self.shared_tags))?;
+        writeln!(f, "\tbiomes: {:+} ({})", self.strength.biome, list(&self.shared_biomes))?;
+        writeln!(f, "\talignment: {:+} ({} / {})", self.strength.alignment, self.alignments.0, self.alignments.1)?;
+        writeln!(f, "\tsource: {:+} ({} / {})", self.strength.source, self.sources.0, self.sources.1)?;
+        write!(
+            f,
+            "\ttext: {:+} ({:.2} alike: {})",
+            self.strength.text,
+            self.text_similarity,
+            list(&self.shared_terms)
+        )
+    }
+}
+
+/// What two monsters have in common before a [`ScoringProfile`] weighs it.
+#[derive(Debug, Clone, Copy, Default)]
+struct Likeness {
+    level_gap: u8,
+    tags: i32,
+    biomes: i32,
+    alignment: bool,
+    source: bool,
+    text: f32,
+}
+
+/// Every loaded monster, plus a graph connecting each monster to the others it is
+/// related to, strongest first, as scored by a [`ScoringProfile`].
 #[derive(Default)]
 pub struct Monsters {
-    vertices: HashMap<MonsterId, Monster>,
+    vertices: Arc<HashMap<MonsterId, Monster>>,
+    /// What each monster has in common with every other, shared between graphs of the
+    /// same monsters so that rescoring only weighs it again.
+    likeness: Arc<HashMap<MonsterId, Vec<(MonsterId, Likeness)>>>,
     adjacency: HashMap<MonsterId, Vec<(MonsterId, Strength)>>,
+    profile: ScoringProfile,
+    text: Arc<TextIndex>,
 }
 
 impl Monsters {
+    /// The graph scored by the default profile.
     pub fn new(monsters: Vec<Monster>) -> Monsters {
-        let mut graph = Monsters::default();
+        Monsters::with_profile(monsters, ScoringProfile::default())
+    }
+
+    pub fn with_profile(monsters: Vec<Monster>, profile: ScoringProfile) -> Monsters {
+        let mut graph = Monsters {
+            profile,
+            ..Monsters::default()
+        };
         graph.build_graph(monsters);
         graph
     }
 
+    /// The same monsters scored by another profile, without comparing them again.
+    pub fn rescore(self: &Monsters, profile: ScoringProfile) -> Monsters {
+        let mut graph = Monsters {
+            vertices: self.vertices.clone(),
+            likeness: self.likeness.clone(),
+            adjacency: HashMap::new(),
+            profile,
+            text: self.text.clone(),
+        };
+        graph.score();
+        graph
+    }
+
+    pub fn profile(self: &Monsters) -> &ScoringProfile {
+        &self.profile
+    }
+
+    /// Every monster, in load order.
     pub fn all(self: &Monsters) -> Vec<&Monster> {
-        self.vertices.values().collect()
+        let mut all: Vec<&Monster> = self.vertices.values().collect();
+        all.sort_by_key(|monster| monster.id);
+        all
     }
 
     pub fn len(self: &Monsters) -> usize {
@@ -184,93 +317,247 @@ impl Monsters {
     }
 
     fn build_graph(self: &mut Monsters, vs: Vec<Monster>) {
-        for v in vs {
-            self.vertices.insert(v.id, v);
+        self.vertices = Arc::new(vs.into_iter().map(|v| (v.id, v)).collect());
+        self.text = Arc::new(TextIndex::new(self.vertices.values()));
+        let all = self.all();
+        let mut likeness: HashMap<MonsterId, Vec<(MonsterId, Likeness)>> = HashMap::new();
+        for (i, v) in all.iter().enumerate() {
+            for other in &all[i + 1..] {
+                let pair = self.likeness_of(v, other);
+                likeness.entry(v.id).or_default().push((other.id, pair));
+                likeness.entry(other.id).or_default().push((v.id, pair));
+            }
         }
-        for v in self.vertices.values() {
-            for other in self.vertices.values() {
-                if v == other {
-                    continue;
-                }
-                let strength = Self::calculate_connection_strength(v, other, false);
-                if strength.total > 0 {
-                    let adjacent_to_from = self.adjacency.entry(v.id).or_default();
-                    adjacent_to_from.push((other.id, strength));
-                }
+        self.likeness = Arc::new(likeness);
+        self.score();
+    }
+
+    /// Links every pair of monsters whose likeness the profile scores above zero.
+    fn score(self: &mut Monsters) {
+        let mut adjacency: HashMap<MonsterId, Vec<(MonsterId, Strength)>> = HashMap::new();
+        for (id, others) in self.likeness.iter() {
+            let adjacents: Vec<(MonsterId, Strength)> = others
+                .iter()
+                .map(|(other, likeness)| (*other, self.weigh(likeness)))
+                .filter(|(_, strength)| strength.total > 0)
+                .collect();
+            if !adjacents.is_empty() {
+                adjacency.insert(*id, adjacents);
             }
         }
-        for adjacents in self.adjacency.values_mut() {
-            adjacents.sort_by(|a, b| b.1.total.cmp(&a.1.total));
+        for adjacents in adjacency.values_mut() {
+            adjacents.sort_by_key(|a| (std::cmp::Reverse(a.1.total), a.0));
         }
+        self.adjacency = adjacency;
     }
 
-    fn calculate_connection_strength(m1: &Monster, m2: &Monster, debug: bool) -> Strength {
-        let common_tags = m1.tags.iter().filter(|t| m2.tags.contains(t)).count() as i32;
-        let common_biomes: i32;
-        if m1.biomes.contains(&"*".to_string()) {
-            common_biomes = m2.biomes.len() as i32;
-        } else if m2.biomes.contains(&"*".to_string()) {
-            common_biomes = m1.biomes.len() as i32;
-        } else {
-            common_biomes = m1.biomes.iter().filter(|t| m2.biomes.contains(t)).count() as i32;
+    fn likeness_of(self: &Monsters, m1: &Monster, m2: &Monster) -> Likeness {
+        Likeness {
+            level_gap: m1.level.abs_diff(m2.level),
+            tags: m1.tags.iter().filter(|t| m2.tags.contains(t)).count() as i32,
+            biomes: biome::shared(&m1.biomes, &m2.biomes) as i32,
+            alignment: m1.alignment == m2.alignment,
+            source: m1.source == m2.source,
+            text: self.text.similarity(m1.id, m2.id),
         }
-        
-        let level_strength = 10 - 7 * (m1.level as i32 - m2.level as i32).abs();
-        let alignment_bonus = if m1.alignment == m2.alignment { 15 } else { 0 };
-        let biome_bonus = 5 * common_biomes.min(3);
-        let tag_bonus = 10 * common_tags;
-        let source_bonus = if m1.source == m2.source { 10 } else { 0 };
-        let result = level_strength + tag_bonus + biome_bonus + alignment_bonus + source_bonus;
-        let strength = Strength {
+    }
+
+    fn weigh(self: &Monsters, likeness: &Likeness) -> Strength {
+        let profile = &self.profile;
+        let level_strength = profile.level - profile.level_step * likeness.level_gap as i32;
+        let alignment_bonus = if likeness.alignment { profile.alignment } else { 0 };
+        let biome_bonus = profile.biome * likeness.biomes.min(profile.biome_cap);
+        let tag_bonus = profile.tag * likeness.tags;
+        let source_bonus = if likeness.source { profile.source } else { 0 };
+        let text_bonus = (profile.text as f32 * likeness.text).round() as i32;
+        let result = level_strength + tag_bonus + biome_bonus + alignment_bonus + source_bonus + text_bonus;
+        Strength {
             total: result,
             level: level_strength,
             tag: tag_bonus,
             biome: biome_bonus,
             alignment: alignment_bonus,
             source: source_bonus,
+            text: text_bonus,
+        }
+    }
+
+    /// Why `from` and `to` are as related as they are under the graph's profile.
+    pub fn explain(self: &Monsters, from: &Monster, to: &Monster) -> Connection {
+        Connection {
+            from: from.name.clone(),
+            to: to.name.clone(),
+            level_gap: from.level.abs_diff(to.level),
+            shared_tags: from.tags.iter().filter(|t| to.tags.contains(t)).cloned().collect(),
+            shared_biomes: biome::common(&from.biomes, &to.biomes),
+            alignments: (from.alignment.clone(), to.alignment.clone()),
+            sources: (from.source.clone(), to.source.clone()),
+            text_similarity: self.text.similarity(from.id, to.id),
+            shared_terms: self.text.shared_terms(from.id, to.id, 5),
+            strength: self.weigh(&self.likeness_of(from, to)),
+        }
+    }
+
+    /// A view of the graph that only sees the monsters in `visible`, or every monster
+    /// for `None`. Views share the graph, so filtering never rebuilds it.
+    pub fn view(self: &Monsters, visible: Option<HashSet<MonsterId>>) -> View<'_> {
+        View { graph: self, visible }
+    }
+}
+
+/// Part of a [`Monsters`] graph: edges to monsters outside the view are skipped.
+pub struct View<'a> {
+    graph: &'a Monsters,
+    visible: Option<HashSet<MonsterId>>,
+}
+
+impl View<'_> {
+    pub fn contains(&self, monster: &Monster) -> bool {
+        self.visible.as_ref().is_none_or(|visible| visible.contains(&monster.id))
+    }
+
+    /// Every visible monster, sorted by id.
+    pub fn monsters(&self) -> Vec<&Monster> {
+        self.graph.all().into_iter().filter(|m| self.contains(m)).collect()
+    }
+
+    /// Every link between two visible monsters with at least `min_strength`, once each,
+    /// ordered by the ids of the monsters at either end.
+    pub fn edges(&self, min_strength: i32) -> Vec<(&Monster, &Monster, &Strength)> {
+        let mut edges = Vec::new();
+        for monster in self.monsters() {
+            for (neighbor, strength) in self.neighbors(monster) {
+                if monster.id < neighbor.id && strength.total >= min_strength {
+                    edges.push((monster, neighbor, strength));
+                }
+            }
+        }
+        edges.sort_by_key(|(from, to, _)| (from.id, to.id));
+        edges
+    }
+
+    /// The visible monsters related to `seed`, strongest first. `seed` itself doesn't
+    /// have to be visible.
+    pub fn neighbors(&self, seed: &Monster) -> impl Iterator<Item = (&Monster, &Strength)> {
+        self.graph
+            .adjacency
+            .get(&seed.id)
+            .into_iter()
+            .flatten()
+            .filter_map(|(id, strength)| self.graph.vertices.get(id).map(|monster| (monster, strength)))
+            .filter(|(monster, _)| self.contains(monster))
+    }
+
+    /// Up to `limit` of the monsters most strongly related to `seed`.
+    pub fn get_adjacent(&self, seed: &Monster, limit: u32) -> Vec<&Monster> {
+        self.neighbors(seed)
+            .take(limit as usize)
+            .map(|(neighbor, _)| neighbor)
+            .collect()
+    }
+
+    /// The `count` strongest and the `count` weakest of `seed`'s links, strongest first
+    /// in both lists. Only monsters with a positive strength count as linked.
+    pub fn links(&self, seed: &Monster, count: usize) -> (Vec<Connection>, Vec<Connection>) {
+        let linked: Vec<&Monster> = self.neighbors(seed).map(|(neighbor, _)| neighbor).collect();
+        let explain = |neighbors: &[&Monster]| -> Vec<Connection> {
+            neighbors.iter().map(|neighbor| self.graph.explain(seed, neighbor)).collect()
         };
-        if debug {
-            println!(
-                "{} -> {}: {} = {}",
-                m1.name, m2.name, result, strength
-            );
+        let strongest = explain(&linked[..count.min(linked.len())]);
+        let weakest = explain(&linked[linked.len().saturating_sub(count)..]);
+        (strongest, weakest)
+    }
+
+    /// The chain of monsters from `from` to `to` whose links are strongest overall, both
+    /// ends included, or `None` if no chain through visible monsters joins them. Each link
+    /// costs the inverse of its strength, so a few strong links beat one weak one. The
+    /// ends don't have to be visible.
+    pub fn path(&self, from: &Monster, to: &Monster) -> Option<Vec<&Monster>> {
+        const LINK_COST: u32 = 10_000;
+        let mut cost: HashMap<MonsterId, u32> = HashMap::from([(from.id, 0)]);
+        let mut previous: HashMap<MonsterId, MonsterId> = HashMap::new();
+        let mut queue = BinaryHeap::from([(Reverse(0), from.id)]);
+        while let Some((Reverse(so_far), id)) = queue.pop() {
+            if id == to.id {
+                break;
+            }
+            if so_far > cost[&id] {
+                continue;
+            }
+            let links = self.graph.adjacency.get(&id).into_iter().flatten();
+            for (next, strength) in links {
+                let visible = *next == to.id || self.visible.as_ref().is_none_or(|v| v.contains(next));
+                let next_cost = so_far + LINK_COST / strength.total as u32;
+                if visible && cost.get(next).is_none_or(|c| next_cost < *c) {
+                    cost.insert(*next, next_cost);
+                    previous.insert(*next, id);
+                    queue.push((Reverse(next_cost), *next));
+                }
+            }
         }
-        return strength;
+
+        let mut path = vec![self.graph.vertices.get(&to.id)?];
+        let mut id = to.id;
+        while id != from.id {
+            id = *previous.get(&id)?;
+            path.push(&self.graph.vertices[&id]);
+        }
+        path.reverse();
+        Some(path)
     }
 
-    pub fn get_adjacent(self: &Monsters, seed: &Monster, limit: u32) -> Vec<&Monster> {
-        let mut adjacent = Vec::new();
-        let mut count = 0;
-        if let Some(neighbors) = self.adjacency.get(&seed.id) {
-            for (id, _) in neighbors {
-                if count >= limit {
-                    break;
+    /// Splits the visible monsters into communities by label propagation: each monster
+    /// starts in its own community and repeatedly joins the one its `top` strongest links
+    /// pull hardest towards, until no monster moves. Monsters are visited in id order, so
+    /// the result is the same every time. Communities come out largest first.
+    pub fn communities(&self, top: usize) -> Vec<Vec<&Monster>> {
+        const MAX_ROUNDS: usize = 50;
+        let members = self.monsters();
+        let mut labels: HashMap<MonsterId, MonsterId> = members.iter().map(|m| (m.id, m.id)).collect();
+        for _ in 0..MAX_ROUNDS {
+            let mut moved = false;
+            for monster in &members {
+                let mut pull: HashMap<MonsterId, i32> = HashMap::new();
+                for (neighbor, strength) in self.neighbors(monster).take(top) {
+                    *pull.entry(labels[&neighbor.id]).or_default() += strength.total;
                 }
-                let neighbor = self.vertices.get(id);
-                Self::calculate_connection_strength(seed, neighbor.unwrap(), true);
-                if let Some(neighbor) = neighbor {
-                    adjacent.push(neighbor);
+                let current = labels[&monster.id];
+                let strongest = pull.values().copied().max().unwrap_or(0);
+                if pull.get(&current).copied().unwrap_or(0) == strongest {
+                    continue;
                 }
-                count = count + 1;
+                let label = pull.iter().filter(|(_, p)| **p == strongest).map(|(l, _)| *l).min().unwrap();
+                labels.insert(monster.id, label);
+                moved = true;
+            }
+            if !moved {
+                break;
             }
         }
-        adjacent
+
+        let mut communities: HashMap<MonsterId, Vec<&Monster>> = HashMap::new();
+        for monster in members {
+            communities.entry(labels[&monster.id]).or_default().push(monster);
+        }
+        let mut communities: Vec<Vec<&Monster>> = communities.into_values().collect();
+        communities.sort_by_key(|community| (Reverse(community.len()), community[0].id));
+        communities
     }
-    
-    pub fn get_neighbor_excluding(self: &Monsters, seed: &Monster, excluding: &Vec<Monster>, distance: &i32) -> &Monster {
-        let options: &Vec<(MonsterId, Strength)> = self.adjacency.get(&seed.id).unwrap();
-        let excluded_ids: Vec<_>= excluding.iter().map(|m| m.id).collect();
+
+    /// The `distance`th most strongly related monster to `seed` that isn't in `excluding`,
+    /// or `None` when `seed` has no such neighbor.
+    pub fn get_neighbor_excluding(&self, seed: &Monster, excluding: &[Monster], distance: &i32) -> Option<&Monster> {
+        let excluded_ids: Vec<_> = excluding.iter().map(|m| m.id).collect();
         let mut count = 0;
-        for (id, strength) in options {
-            count = count + 1;
+        for (neighbor, _) in self.neighbors(seed) {
+            count += 1;
             if count < *distance {
                 continue;
             }
-            if !excluded_ids.contains(id) {
-                println!("{} -> {} @ {}: {}", seed.name, self.vertices.get(id).unwrap().name, distance, strength);
-                return self.vertices.get(id).unwrap();
+            if !excluded_ids.contains(&neighbor.id) {
+                return Some(neighbor);
             }
         }
-        panic!("No valid neighbors found");
+        None
     }
 }
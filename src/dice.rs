@@ -0,0 +1,261 @@
+//! Dice notation such as `2d4`, `d6+1` or a plain `3`, and the damage expressions on
+//! stat blocks built from them, such as `1d8 + curse` or `1d4 + 1d4 fire`.
+
+use rand::Rng;
+use std::fmt;
+
+/// The most dice [`Dice::parse`] accepts in one expression.
+pub const MAX_COUNT: u32 = 100;
+/// The most sides [`Dice::parse`] accepts on a die.
+pub const MAX_SIDES: u32 = 1000;
+
+/// `count` dice with `sides` sides each, plus `modifier`. A plain number has no dice.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Dice {
+    pub count: u32,
+    pub sides: u32,
+    pub modifier: i32,
+}
+
+impl Dice {
+    /// A fixed number with no dice to roll.
+    pub const fn fixed(value: i32) -> Dice {
+        Dice {
+            count: 0,
+            sides: 0,
+            modifier: value,
+        }
+    }
+
+    pub const fn new(count: u32, sides: u32) -> Dice {
+        Dice {
+            count,
+            sides,
+            modifier: 0,
+        }
+    }
+
+    /// Reads `NdS`, `NdS+M`, `NdS-M`, `dS` (one die) or a plain number, ignoring spaces
+    /// and case.
+    pub fn parse(text: &str) -> Result<Dice, String> {
+        let error = || format!("'{}' isn't dice like 2d6+1", text);
+        let compact: String = text.chars().filter(|c| !c.is_whitespace()).collect::<String>().to_lowercase();
+        let (dice, modifier) = match compact.find(['+', '-']) {
+            Some(i) if i > 0 => (&compact[..i], compact[i..].trim_start_matches('+')),
+            _ => (compact.as_str(), "0"),
+        };
+        let modifier: i32 = modifier.parse().map_err(|_| error())?;
+        match dice.split_once('d') {
+            Some((count, sides)) => {
+                let count = if count.is_empty() { 1 } else { count.parse().map_err(|_| error())? };
+                let sides: u32 = sides.parse().map_err(|_| error())?;
+                if sides == 0 {
+                    return Err(error());
+                }
+                if count > MAX_COUNT || sides > MAX_SIDES {
+                    return Err(format!(
+                        "'{}' rolls more than {} dice or dice with more than {} sides",
+                        text, MAX_COUNT, MAX_SIDES
+                    ));
+                }
+                Ok(Dice { count, sides, modifier })
+            }
+            None => dice
+                .parse::<i32>()
+                .ok()
+                .and_then(|value| value.checked_add(modifier))
+                .map(Dice::fixed)
+                .ok_or_else(error),
+        }
+    }
+
+    pub fn roll(&self, rng: &mut impl Rng) -> i32 {
+        (0..self.count).map(|_| rng.gen_range(1..=self.sides) as i32).sum::<i32>() + self.modifier
+    }
+
+    pub fn min(&self) -> i32 {
+        i32::try_from(self.count).unwrap_or(i32::MAX).saturating_add(self.modifier)
+    }
+
+    /// The highest roll, capped at `i32::MAX` for dice built by hand that could roll more.
+    pub fn max(&self) -> i32 {
+        self.count
+            .checked_mul(self.sides)
+            .and_then(|total| i32::try_from(total).ok())
+            .map_or(i32::MAX, |total| total.saturating_add(self.modifier))
+    }
+
+    pub fn average(&self) -> f64 {
+        self.count as f64 * (self.sides as f64 + 1.0) / 2.0 + self.modifier as f64
+    }
+}
+
+/// Dice that deal damage, with the kind of damage if the expression names one.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DamageTerm {
+    pub dice: Dice,
+    /// What follows the dice, e.g. `fire` in `1d4 fire`.
+    pub kind: Option<String>,
+}
+
+/// A damage expression such as `1d8 + 2 + curse`: dice terms added together, plus
+/// riders (`curse`) that aren't rolled. Only the first of several alternatives
+/// (`1d4 or 1d6`, `1d6/1d8`) is rolled.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Damage {
+    pub terms: Vec<DamageTerm>,
+    pub riders: Vec<String>,
+    /// The damage the attack may deal instead, e.g. `1d8` in `1d6/1d8`.
+    pub alternatives: Vec<String>,
+}
+
+impl Damage {
+    /// Reads terms joined by `+`. A term that doesn't start with dice or a number is a
+    /// rider. Fails when nothing in the expression can be rolled.
+    pub fn parse(text: &str) -> Result<Damage, String> {
+        let mut damage = Damage::default();
+        for term in text.split('+').map(str::trim).filter(|t| !t.is_empty()) {
+            let end = term.find(|c: char| c.is_whitespace() || c == '/').unwrap_or(term.len());
+            let dice = match Dice::parse(&term[..end]) {
+                Ok(dice) => dice,
+                Err(_) => {
+                    damage.riders.push(term.to_string());
+                    continue;
+                }
+            };
+            let rest = term[end..].trim();
+            let alternative = rest.strip_prefix('/').or_else(|| rest.strip_prefix("or "));
+            let kind = if let Some(alternative) = alternative {
+                damage.alternatives.push(alternative.trim().to_string());
+                None
+            } else {
+                Some(rest.to_string()).filter(|kind| !kind.is_empty())
+            };
+            damage.terms.push(DamageTerm { dice, kind });
+        }
+        if damage.terms.is_empty() {
+            return Err(format!("'{}' has no dice to roll", text));
+        }
+        Ok(damage)
+    }
+
+    pub fn roll(&self, rng: &mut impl Rng) -> i32 {
+        self.terms.iter().map(|term| term.dice.roll(rng)).sum::<i32>().max(0)
+    }
+
+    pub fn min(&self) -> i32 {
+        self.terms.iter().map(|term| term.dice.min()).sum::<i32>().max(0)
+    }
+
+    pub fn max(&self) -> i32 {
+        self.terms.iter().map(|term| term.dice.max()).sum()
+    }
+
+    pub fn average(&self) -> f64 {
+        self.terms.iter().map(|term| term.dice.average()).sum()
+    }
+}
+
+/// The expression as [`Damage::parse`] reads it, e.g. `1d4 + 1d4 fire + burn`.
+impl fmt::Display for Damage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let terms = self.terms.iter().map(|term| match &term.kind {
+            Some(kind) => format!("{} {}", term.dice, kind),
+            None => term.dice.to_string(),
+        });
+        let parts: Vec<String> = terms.chain(self.riders.iter().cloned()).collect();
+        write!(f, "{}", parts.join(" + "))?;
+        for alternative in &self.alternatives {
+            write!(f, " or {}", alternative)?;
+        }
+        Ok(())
+    }
+}
+
+/// The dice in the notation [`Dice::parse`] reads, e.g. `2d6+1`.
+impl fmt::Display for Dice {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.count == 0 {
+            return write!(f, "{}", self.modifier);
+        }
+        write!(f, "{}d{}", self.count, self.sides)?;
+        match self.modifier {
+            0 => Ok(()),
+            m => write!(f, "{:+}", m),
+        }
+    }
+}
+
+/// Hit points rolled the Shadowdark way: a d8 per level (a d4 at level 0) plus the
+/// constitution modifier, and at least 1.
+pub fn roll_hit_points(level: u8, constitution: i8, rng: &mut impl Rng) -> u16 {
+    let dice = match level {
+        0 => Dice::new(1, 4),
+        level => Dice::new(level as u32, 8),
+    };
+    (dice.roll(rng) + constitution as i32).max(1) as u16
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::dice::{self, Damage, Dice};
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn parses_and_rolls() {
+        assert_eq!(Dice::parse("2d4"), Ok(Dice::new(2, 4)));
+        assert_eq!(Dice::parse("d6 + 1").unwrap().to_string(), "1d6+1");
+        assert_eq!(Dice::parse("3d8-2").unwrap().min(), 1);
+        assert_eq!(Dice::parse("1"), Ok(Dice::fixed(1)));
+        assert!(Dice::parse("2d").is_err());
+        assert!(Dice::parse("many").is_err());
+
+        let dice = Dice::parse("2d4").unwrap();
+        let mut rng = StdRng::seed_from_u64(1);
+        for _ in 0..100 {
+            assert!((dice.min()..=dice.max()).contains(&dice.roll(&mut rng)));
+        }
+        assert_eq!(dice.average(), 5.0);
+    }
+
+    #[test]
+    fn rejects_oversized_dice() {
+        assert_eq!(Dice::parse("100d1000").unwrap().max(), 100_000);
+        assert!(Dice::parse("101d6").is_err());
+        assert!(Dice::parse("1d1001").is_err());
+        assert!(Dice::parse("100000d100000").is_err());
+        assert!(Dice::parse("2147483647+1").is_err());
+        assert_eq!(Dice::new(100_000, 100_000).max(), i32::MAX);
+    }
+
+    #[test]
+    fn damage_expressions() {
+        let damage = Damage::parse("1d8 + curse").unwrap();
+        assert_eq!(damage.terms.len(), 1);
+        assert_eq!(damage.riders, vec!["curse"]);
+        assert_eq!(damage.average(), 4.5);
+
+        let damage = Damage::parse("1d4 + 1d4 fire").unwrap();
+        assert_eq!(damage.terms[1].kind.as_deref(), Some("fire"));
+        assert_eq!((damage.min(), damage.max(), damage.average()), (2, 8, 5.0));
+        assert_eq!(damage.to_string(), "1d4 + 1d4 fire");
+
+        let damage = Damage::parse("1d8 + 2").unwrap();
+        assert_eq!((damage.min(), damage.max()), (3, 10));
+
+        let damage = Damage::parse("1d6/1d8").unwrap();
+        assert_eq!(damage.max(), 6);
+        assert_eq!(damage.alternatives, vec!["1d8"]);
+        assert_eq!(Damage::parse("1d4 or 1d6").unwrap().to_string(), "1d4 or 1d6");
+        assert!(Damage::parse("poison").is_err());
+
+        let mut rng = StdRng::seed_from_u64(1);
+        let damage = Damage::parse("3d6").unwrap();
+        for _ in 0..100 {
+            assert!((3..=18).contains(&damage.roll(&mut rng)));
+            assert!((1..=4).contains(&dice::roll_hit_points(0, -3, &mut rng)));
+            assert!((4..=26).contains(&dice::roll_hit_points(3, 2, &mut rng)));
+        }
+    }
+}
@@ -0,0 +1,185 @@
+//! Encounters sized for a party: a leader and groups of weaker followers whose levels
+//! add up to a budget set by the party and the difficulty.
+
+use crate::data::Monster;
+use std::fmt;
+
+/// The most of any one kind of follower in an encounter.
+pub const MAX_APPEARING: u32 = 12;
+/// The most kinds of follower in an encounter.
+const FOLLOWER_KINDS: usize = 2;
+
+/// How hard an encounter should be, which scales its level budget.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Difficulty {
+    Easy,
+    #[default]
+    Normal,
+    Hard,
+    Deadly,
+}
+
+impl Difficulty {
+    pub fn parse(name: &str) -> Result<Difficulty, String> {
+        match name.to_lowercase().as_str() {
+            "easy" => Ok(Difficulty::Easy),
+            "normal" => Ok(Difficulty::Normal),
+            "hard" => Ok(Difficulty::Hard),
+            "deadly" => Ok(Difficulty::Deadly),
+            _ => Err(format!("Unknown difficulty '{}'; try easy, normal, hard or deadly", name)),
+        }
+    }
+
+    /// The budget as a multiple of the party's total levels, in halves.
+    fn halves(self) -> u32 {
+        match self {
+            Difficulty::Easy => 1,
+            Difficulty::Normal => 2,
+            Difficulty::Hard => 3,
+            Difficulty::Deadly => 4,
+        }
+    }
+}
+
+impl fmt::Display for Difficulty {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Difficulty::Easy => "easy",
+            Difficulty::Normal => "normal",
+            Difficulty::Hard => "hard",
+            Difficulty::Deadly => "deadly",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// The characters an encounter is for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Party {
+    pub size: u8,
+    /// The characters' average level.
+    pub level: u8,
+}
+
+impl Party {
+    /// The total monster levels an encounter of this difficulty should have: the party's
+    /// total levels, halved for easy and up to doubled for deadly, and at least 1.
+    pub fn budget(&self, difficulty: Difficulty) -> u32 {
+        (self.size as u32 * self.level.max(1) as u32 * difficulty.halves() / 2).max(1)
+    }
+}
+
+impl Default for Party {
+    fn default() -> Self {
+        Party { size: 4, level: 1 }
+    }
+}
+
+/// A leader and its followers. The leader comes first in [`Encounter::groups`].
+#[derive(Debug, Clone)]
+pub struct Encounter {
+    pub party: Party,
+    pub difficulty: Difficulty,
+    pub budget: u32,
+    /// Each kind of monster with how many of it there are, leader first.
+    pub groups: Vec<(Monster, u32)>,
+}
+
+impl Encounter {
+    /// Builds an encounter from `candidates`, most fitting first. The leader is the
+    /// highest-level candidate that fits the budget (the lowest-level one if none do),
+    /// and up to two kinds of lower-level candidates fill the rest in multiples.
+    pub fn build(candidates: &[Monster], party: Party, difficulty: Difficulty) -> Option<Encounter> {
+        let budget = party.budget(difficulty);
+        let leader = candidates
+            .iter()
+            .filter(|m| cost(m) <= budget)
+            .min_by_key(|m| std::cmp::Reverse(m.level))
+            .or_else(|| candidates.iter().min_by_key(|m| m.level))?;
+        let mut remaining = budget.saturating_sub(cost(leader));
+        let mut groups = vec![(leader.clone(), 1)];
+
+        let followers: Vec<&Monster> = candidates
+            .iter()
+            .filter(|m| m.level < leader.level && cost(m) <= remaining)
+            .take(FOLLOWER_KINDS)
+            .collect();
+        for (i, follower) in followers.iter().enumerate() {
+            // Split what's left evenly between the kinds still to come.
+            let share = remaining.div_ceil((followers.len() - i) as u32);
+            let count = (share / cost(follower)).clamp(1, MAX_APPEARING);
+            if count * cost(follower) > remaining {
+                continue;
+            }
+            remaining -= count * cost(follower);
+            groups.push(((*follower).clone(), count));
+        }
+        Some(Encounter {
+            party,
+            difficulty,
+            budget,
+            groups,
+        })
+    }
+
+    /// The total levels of every monster in the encounter.
+    pub fn total(&self) -> u32 {
+        self.groups.iter().map(|(monster, count)| cost(monster) * count).sum()
+    }
+}
+
+/// The heading for an encounter's monster list.
+impl fmt::Display for Encounter {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} encounter for {} level {} characters: {} of {} levels",
+            self.difficulty,
+            self.party.size,
+            self.party.level,
+            self.total(),
+            self.budget
+        )
+    }
+}
+
+/// What a monster takes out of the budget. Level 0 monsters still count for 1.
+fn cost(monster: &Monster) -> u32 {
+    monster.level.max(1) as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::encounter::{Difficulty, Encounter, Party, MAX_APPEARING};
+    use crate::monster_loader;
+
+    #[test]
+    fn budgets() {
+        let party = Party { size: 4, level: 3 };
+        assert_eq!(party.budget(Difficulty::Easy), 6);
+        assert_eq!(party.budget(Difficulty::Normal), 12);
+        assert_eq!(party.budget(Difficulty::Deadly), 24);
+        assert_eq!(Party { size: 1, level: 1 }.budget(Difficulty::Easy), 1);
+        assert_eq!(Difficulty::parse("Hard"), Ok(Difficulty::Hard));
+        assert!(Difficulty::parse("brutal").is_err());
+    }
+
+    #[test]
+    fn leader_and_followers_fit_the_budget() {
+        let monsters = monster_loader::load_monsters(&[]).unwrap();
+        let find = |name: &str| monsters.iter().find(|m| m.name == name).unwrap().clone();
+        let candidates = vec![find("GOBLIN"), find("GOBLIN, BOSS"), find("HOBGOBLIN"), find("BUGBEAR")];
+        let party = Party { size: 4, level: 2 };
+        let encounter = Encounter::build(&candidates, party, Difficulty::Normal).unwrap();
+        assert!(encounter.total() <= encounter.budget);
+        let (leader, count) = &encounter.groups[0];
+        assert_eq!(*count, 1);
+        assert!(encounter.groups[1..].iter().all(|(m, n)| m.level < leader.level && *n <= MAX_APPEARING));
+        assert!(encounter.groups.len() > 1);
+
+        // Too small a budget for anyone still gets the weakest candidate.
+        let tiny = Encounter::build(&candidates[2..], Party { size: 1, level: 1 }, Difficulty::Easy).unwrap();
+        assert_eq!(tiny.groups.len(), 1);
+        assert!(Encounter::build(&[], party, Difficulty::Easy).is_none());
+    }
+}
@@ -0,0 +1,211 @@
+//! The monster graph in formats other tools read: Graphviz DOT, GraphML and a JSON
+//! edge list. Nodes carry each monster's level, alignment, tags, biomes and source;
+//! edges carry the total strength as their weight and its breakdown.
+
+use crate::data::{Monster, MonsterId, Strength, View};
+use serde::Serialize;
+
+/// Which format [`export`] writes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GraphFormat {
+    Dot,
+    GraphMl,
+    Json,
+}
+
+impl GraphFormat {
+    pub fn parse(name: &str) -> Result<GraphFormat, String> {
+        match name.to_lowercase().as_str() {
+            "dot" => Ok(GraphFormat::Dot),
+            "graphml" => Ok(GraphFormat::GraphMl),
+            "json" => Ok(GraphFormat::Json),
+            _ => Err(format!("Unknown graph format '{}'; try dot, graphml or json", name)),
+        }
+    }
+}
+
+/// The monsters in `view` and the links between them with at least `min_strength`.
+pub fn export(view: &View, min_strength: i32, format: GraphFormat) -> String {
+    let monsters = view.monsters();
+    let edges = view.edges(min_strength);
+    match format {
+        GraphFormat::Dot => dot(&monsters, &edges),
+        GraphFormat::GraphMl => graphml(&monsters, &edges),
+        GraphFormat::Json => json(&monsters, &edges),
+    }
+}
+
+type Edge<'a> = (&'a Monster, &'a Monster, &'a Strength);
+
+/// The parts of a strength breakdown, in the order they are written.
+fn breakdown(strength: &Strength) -> [(&'static str, i32); 6] {
+    [
+        ("level", strength.level),
+        ("tag", strength.tag),
+        ("biome", strength.biome),
+        ("alignment", strength.alignment),
+        ("source", strength.source),
+        ("text", strength.text),
+    ]
+}
+
+fn dot(monsters: &[&Monster], edges: &[Edge]) -> String {
+    let quote = |text: &str| format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""));
+    let mut out = String::from("graph monsters {\n");
+    for monster in monsters {
+        out.push_str(&format!(
+            "  {} [label={}, level={}, alignment={}, tags={}, biomes={}, source={}];\n",
+            monster.id,
+            quote(&monster.name),
+            monster.level,
+            quote(&monster.alignment),
+            quote(&monster.tags.join(",")),
+            quote(&monster.biomes.join(",")),
+            quote(&monster.source)
+        ));
+    }
+    for (from, to, strength) in edges {
+        let parts: Vec<String> = breakdown(strength).iter().map(|(k, v)| format!(", {}={}", k, v)).collect();
+        out.push_str(&format!("  {} -- {} [weight={}{}];\n", from.id, to.id, strength.total, parts.concat()));
+    }
+    out.push_str("}\n");
+    out
+}
+
+fn graphml(monsters: &[&Monster], edges: &[Edge]) -> String {
+    let escape = |text: &str| {
+        text.replace('&', "&amp;")
+            .replace('<', "&lt;")
+            .replace('>', "&gt;")
+            .replace('"', "&quot;")
+    };
+    let mut out = String::from(concat!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n",
+        "<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">\n",
+        "  <key id=\"name\" for=\"node\" attr.name=\"name\" attr.type=\"string\"/>\n",
+        "  <key id=\"level\" for=\"node\" attr.name=\"level\" attr.type=\"int\"/>\n",
+        "  <key id=\"alignment\" for=\"node\" attr.name=\"alignment\" attr.type=\"string\"/>\n",
+        "  <key id=\"tags\" for=\"node\" attr.name=\"tags\" attr.type=\"string\"/>\n",
+        "  <key id=\"biomes\" for=\"node\" attr.name=\"biomes\" attr.type=\"string\"/>\n",
+        "  <key id=\"source\" for=\"node\" attr.name=\"source\" attr.type=\"string\"/>\n",
+        "  <key id=\"weight\" for=\"edge\" attr.name=\"weight\" attr.type=\"int\"/>\n",
+    ));
+    for (key, _) in breakdown(&Strength::default()) {
+        out.push_str(&format!(
+            "  <key id=\"{0}_strength\" for=\"edge\" attr.name=\"{0}\" attr.type=\"int\"/>\n",
+            key
+        ));
+    }
+    out.push_str("  <graph id=\"monsters\" edgedefault=\"undirected\">\n");
+    for monster in monsters {
+        out.push_str(&format!("    <node id=\"n{}\">\n", monster.id));
+        let data = [
+            ("name", escape(&monster.name)),
+            ("level", monster.level.to_string()),
+            ("alignment", escape(&monster.alignment)),
+            ("tags", escape(&monster.tags.join(","))),
+            ("biomes", escape(&monster.biomes.join(","))),
+            ("source", escape(&monster.source)),
+        ];
+        for (key, value) in data {
+            out.push_str(&format!("      <data key=\"{}\">{}</data>\n", key, value));
+        }
+        out.push_str("    </node>\n");
+    }
+    for (from, to, strength) in edges {
+        out.push_str(&format!("    <edge source=\"n{}\" target=\"n{}\">\n", from.id, to.id));
+        out.push_str(&format!("      <data key=\"weight\">{}</data>\n", strength.total));
+        for (key, value) in breakdown(strength) {
+            out.push_str(&format!("      <data key=\"{}_strength\">{}</data>\n", key, value));
+        }
+        out.push_str("    </edge>\n");
+    }
+    out.push_str("  </graph>\n</graphml>\n");
+    out
+}
+
+#[derive(Serialize)]
+struct JsonGraph<'a> {
+    nodes: Vec<JsonNode<'a>>,
+    edges: Vec<JsonEdge<'a>>,
+}
+
+#[derive(Serialize)]
+struct JsonNode<'a> {
+    id: MonsterId,
+    name: &'a str,
+    level: u8,
+    alignment: &'a str,
+    tags: &'a [String],
+    biomes: &'a [String],
+    source: &'a str,
+}
+
+#[derive(Serialize)]
+struct JsonEdge<'a> {
+    from: MonsterId,
+    to: MonsterId,
+    strength: &'a Strength,
+}
+
+fn json(monsters: &[&Monster], edges: &[Edge]) -> String {
+    let graph = JsonGraph {
+        nodes: monsters
+            .iter()
+            .map(|monster| JsonNode {
+                id: monster.id,
+                name: &monster.name,
+                level: monster.level,
+                alignment: &monster.alignment,
+                tags: &monster.tags,
+                biomes: &monster.biomes,
+                source: &monster.source,
+            })
+            .collect(),
+        edges: edges
+            .iter()
+            .map(|(from, to, strength)| JsonEdge {
+                from: from.id,
+                to: to.id,
+                strength,
+            })
+            .collect(),
+    };
+    serde_json::to_string(&graph).unwrap() + "\n"
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::export::{self, GraphFormat};
+    use crate::monster_loader;
+
+    #[test]
+    fn exports_every_format() {
+        let monsters = monster_loader::get_monster_graph(&[]).unwrap();
+        let goblins = monsters
+            .all()
+            .into_iter()
+            .filter(|m| m.name.contains("GOBLIN"))
+            .map(|m| m.id)
+            .collect();
+        let view = monsters.view(Some(goblins));
+        let edges = view.edges(20).len();
+        assert!(edges > 0);
+
+        let dot = export::export(&view, 20, GraphFormat::Dot);
+        assert!(dot.starts_with("graph monsters {\n"));
+        assert!(dot.contains("[label=\"GOBLIN\", level=1"));
+        assert_eq!(dot.matches(" -- ").count(), edges);
+
+        let graphml = export::export(&view, 20, GraphFormat::GraphMl);
+        assert_eq!(graphml.matches("<edge ").count(), edges);
+        assert!(graphml.contains("<data key=\"name\">GOBLIN</data>"));
+
+        let json: serde_json::Value =
+            serde_json::from_str(&export::export(&view, 20, GraphFormat::Json)).unwrap();
+        assert_eq!(json["edges"].as_array().unwrap().len(), edges);
+        assert!(json["edges"][0]["strength"]["total"].as_i64().unwrap() >= 20);
+        assert_eq!(GraphFormat::parse("GraphML"), Ok(GraphFormat::GraphMl));
+        assert!(GraphFormat::parse("svg").is_err());
+    }
+}
@@ -0,0 +1,87 @@
+//! Factions: communities of closely related monsters, named after what most of their
+//! members have in common.
+
+use crate::biome;
+use crate::data::Monster;
+use std::collections::HashMap;
+use std::fmt;
+
+/// A group of monsters more strongly linked to each other than to anyone else.
+#[derive(Debug, Clone)]
+pub struct Faction {
+    /// The most common tag and biome and the leader, e.g. `undead of the tomb led by
+    /// VAMPIRE`.
+    pub name: String,
+    pub tag: Option<String>,
+    pub biome: Option<String>,
+    /// The highest-level member, the first loaded on a tie.
+    pub leader: Monster,
+    pub monsters: Vec<Monster>,
+}
+
+impl Faction {
+    /// Names a faction, or `None` if it has no monsters to lead it.
+    pub fn new(monsters: Vec<Monster>) -> Option<Faction> {
+        let leader = monsters.iter().rev().max_by_key(|m| m.level)?.clone();
+        let tag = most_common(monsters.iter().flat_map(|m| &m.tags));
+        let biome = most_common(monsters.iter().flat_map(|m| &m.biomes).filter(|b| *b != biome::ANY));
+        let kind = match (&tag, &biome) {
+            (Some(tag), Some(biome)) => format!("{} of the {}", tag, biome),
+            (Some(tag), None) => tag.clone(),
+            (None, Some(biome)) => format!("monsters of the {}", biome),
+            (None, None) => "monsters".to_string(),
+        };
+        Some(Faction {
+            name: format!("{} led by {}", kind, leader.name),
+            tag,
+            biome,
+            leader,
+            monsters,
+        })
+    }
+}
+
+impl fmt::Display for Faction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} ({} monsters)", self.name, self.monsters.len())
+    }
+}
+
+/// The value that turns up most often, the alphabetically first on a tie.
+fn most_common<'a>(values: impl Iterator<Item = &'a String>) -> Option<String> {
+    let mut counts: HashMap<&String, usize> = HashMap::new();
+    for value in values {
+        *counts.entry(value).or_default() += 1;
+    }
+    counts
+        .into_iter()
+        .max_by(|(a, x), (b, y)| x.cmp(y).then(b.cmp(a)))
+        .map(|(value, _)| value.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::faction::{self, Faction};
+    use crate::monster_loader;
+
+    #[test]
+    fn named_after_common_tag_and_biome() {
+        let values = ["swamp", "forest", "swamp", "forest"].map(String::from);
+        assert_eq!(faction::most_common(values.iter()), Some("forest".to_string()));
+
+        let monsters = monster_loader::load_monsters(&[]).unwrap();
+        let undead: Vec<_> = monsters
+            .into_iter()
+            .filter(|m| m.tags.contains(&"undead".to_string()))
+            .take(5)
+            .collect();
+        let faction = Faction::new(undead).unwrap();
+        assert_eq!(faction.tag.as_deref(), Some("undead"));
+        assert!(faction.name.starts_with("undead"));
+        assert!(faction.monsters.iter().all(|m| m.level <= faction.leader.level));
+        assert!(faction.name.ends_with(&format!("led by {}", faction.leader.name)));
+        assert_eq!(faction.to_string(), format!("{} (5 monsters)", faction.name));
+
+        assert!(Faction::new(Vec::new()).is_none());
+    }
+}
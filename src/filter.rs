@@ -0,0 +1,156 @@
+//! Filters that take more than one value, and the text syntax used to enter them.
+//!
+//! A value list such as `forest,swamp` matches monsters with either value, `undead+fey`
+//! only monsters with both, and a value starting with `-` rules monsters out:
+//! `undead,-dragon` is any undead that isn't a dragon. Once a list contains a `+`, every
+//! value in it is needed. Levels are a single level (`4`) or an inclusive range (`3-5`).
+
+use std::fmt;
+use std::ops::RangeInclusive;
+
+/// Whether a monster needs any or all of a filter's values.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum Match {
+    #[default]
+    Any,
+    All,
+}
+
+/// Values to look for and values to rule out. An empty filter matches everything.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ValueFilter {
+    pub values: Vec<String>,
+    pub matching: Match,
+    pub excluded: Vec<String>,
+}
+
+impl ValueFilter {
+    /// A filter for exactly one value, or an empty filter for an empty string.
+    pub fn single(value: &str) -> ValueFilter {
+        ValueFilter {
+            values: if value.is_empty() { Vec::new() } else { vec![value.to_string()] },
+            ..ValueFilter::default()
+        }
+    }
+
+    /// Parses a value list. A `-` on its own excludes nothing and is an error.
+    pub fn parse(text: &str) -> Result<ValueFilter, String> {
+        let mut filter = ValueFilter {
+            matching: if text.contains('+') { Match::All } else { Match::Any },
+            ..ValueFilter::default()
+        };
+        for value in text.split([',', '+']).map(str::trim).filter(|v| !v.is_empty()) {
+            match value.strip_prefix('-') {
+                Some(excluded) if excluded.trim().is_empty() => {
+                    return Err(format!("'{}' has a '-' with nothing to exclude", text))
+                }
+                Some(excluded) => filter.excluded.push(excluded.trim().to_string()),
+                None => filter.values.push(value.to_string()),
+            }
+        }
+        Ok(filter)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty() && self.excluded.is_empty()
+    }
+
+    /// The same filter with `normalize` applied to every value.
+    pub fn map(&self, normalize: fn(&str) -> String) -> ValueFilter {
+        ValueFilter {
+            values: self.values.iter().map(|v| normalize(v)).collect(),
+            matching: self.matching,
+            excluded: self.excluded.iter().map(|v| normalize(v)).collect(),
+        }
+    }
+
+    /// Whether something passes the filter, given whether it has a wanted value and
+    /// whether it has an excluded one.
+    pub fn matches(&self, has: impl Fn(&str) -> bool, has_excluded: impl Fn(&str) -> bool) -> bool {
+        let wanted = match self.matching {
+            Match::Any => self.values.is_empty() || self.values.iter().any(|v| has(v)),
+            Match::All => self.values.iter().all(|v| has(v)),
+        };
+        wanted && !self.excluded.iter().any(|v| has_excluded(v))
+    }
+}
+
+/// The filter in the syntax [`ValueFilter::parse`] reads.
+impl fmt::Display for ValueFilter {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let separator = match self.matching {
+            Match::Any => ",",
+            Match::All => "+",
+        };
+        let mut parts = Vec::new();
+        if !self.values.is_empty() {
+            parts.push(self.values.join(separator));
+        }
+        parts.extend(self.excluded.iter().map(|v| format!("-{}", v)));
+        write!(f, "{}", parts.join(","))
+    }
+}
+
+/// Parses `4` or `3-5` into an inclusive range of levels.
+pub fn parse_levels(text: &str) -> Result<RangeInclusive<u8>, String> {
+    let number = |n: &str| {
+        n.trim()
+            .parse::<u8>()
+            .map_err(|_| format!("level must be a number or a range like 3-5, not '{}'", text))
+    };
+    let levels = match text.split_once('-') {
+        Some((min, max)) => number(min)?..=number(max)?,
+        None => number(text)?..=number(text)?,
+    };
+    if levels.is_empty() {
+        return Err(format!("level range '{}' is empty", text));
+    }
+    Ok(levels)
+}
+
+/// A level range as [`parse_levels`] reads it.
+pub fn format_levels(levels: &RangeInclusive<u8>) -> String {
+    if levels.start() == levels.end() {
+        levels.start().to_string()
+    } else {
+        format!("{}-{}", levels.start(), levels.end())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::filter::{self, Match, ValueFilter};
+
+    #[test]
+    fn parses_value_lists() {
+        let filter = ValueFilter::parse("undead, fey,-dragon").unwrap();
+        assert_eq!(filter.values, vec!["undead", "fey"]);
+        assert_eq!(filter.matching, Match::Any);
+        assert_eq!(filter.excluded, vec!["dragon"]);
+        assert_eq!(filter.to_string(), "undead,fey,-dragon");
+
+        let filter = ValueFilter::parse("undead+fey,-dragon").unwrap();
+        assert_eq!(filter.matching, Match::All);
+        assert_eq!(filter.to_string(), "undead+fey,-dragon");
+        assert!(ValueFilter::parse("undead,-").is_err());
+    }
+
+    #[test]
+    fn matches_any_all_and_excluded() {
+        let tags = ["undead", "fey"];
+        let has = |v: &str| tags.contains(&v);
+        assert!(ValueFilter::parse("undead,beast").unwrap().matches(has, has));
+        assert!(!ValueFilter::parse("undead+beast").unwrap().matches(has, has));
+        assert!(!ValueFilter::parse("undead,-fey").unwrap().matches(has, has));
+        assert!(ValueFilter::parse("-dragon").unwrap().matches(has, has));
+    }
+
+    #[test]
+    fn level_ranges() {
+        assert_eq!(filter::parse_levels("3-5"), Ok(3..=5));
+        assert_eq!(filter::parse_levels("4"), Ok(4..=4));
+        assert!(filter::parse_levels("5-3").is_err());
+        assert!(filter::parse_levels("three").is_err());
+        assert_eq!(filter::format_levels(&(3..=5)), "3-5");
+    }
+}
@@ -0,0 +1,41 @@
+//! Finds and groups Shadowdark monsters.
+//!
+//! [`monster_loader`] reads the bundled monster packs (and any user packs) into a
+//! [`Monsters`] graph whose edges connect related monsters. A [`MonsterWrangler`] wraps
+//! that graph, and a [`Choices`] holds the filters used to list, search and pick from it,
+//! including generating clusters and walks of related monsters. Each [`Monster`] can be
+//! rendered in any [`OutputFormat`]. Picks that find nothing return a [`PickError`]
+//! explaining which filters to relax.
+//!
+//! ```no_run
+//! use randommon::{monster_loader, MonsterWrangler, OutputFormat};
+//!
+//! let monsters = monster_loader::get_monster_graph(&[]).unwrap();
+//! let wrangler = MonsterWrangler::new(monsters);
+//! let choices = wrangler.choices().with_biome("swamp".to_string()).with_randomness(Some(1));
+//! for monster in choices.cluster(5, &wrangler).unwrap() {
+//!     println!("{}", monster.render(OutputFormat::Standard));
+//! }
+//! ```
+
+pub mod biome;
+pub mod data;
+pub mod dice;
+pub mod encounter;
+pub mod export;
+pub mod faction;
+pub mod filter;
+pub mod lint;
+pub mod monster_loader;
+pub mod monster_wrangler;
+pub mod rarity;
+pub mod scoring;
+pub mod sources;
+pub mod stat_block;
+pub mod table;
+pub mod text;
+pub mod walk;
+pub mod wandering;
+
+pub use data::{Monster, Monsters, OutputFormat};
+pub use monster_wrangler::{Choices, MonsterWrangler, PickError};
@@ -0,0 +1,241 @@
+use crate::biome;
+use crate::data::RawMonster;
+use crate::dice::Dice;
+use crate::rarity::Rarity;
+use crate::monster_loader::{self, Pack};
+use crate::stat_block::{parse_movement, Movement, StatBlock};
+use std::collections::HashMap;
+use std::fmt;
+
+/// One problem with a monster pack.
+pub struct Finding {
+    pub file: String,
+    /// 1-based position of the entry in its file, or `None` for problems with the file itself.
+    pub entry: Option<usize>,
+    pub monster: Option<String>,
+    pub message: String,
+}
+
+impl fmt::Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match (self.entry, &self.monster) {
+            (Some(entry), Some(monster)) => {
+                write!(f, "{}:{} ({}): {}", self.file, entry, monster, self.message)
+            }
+            _ => write!(f, "{}: {}", self.file, self.message),
+        }
+    }
+}
+
+/// Checks every entry of every pack, returning the problems in pack and entry order.
+pub fn lint(packs: &[Pack]) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    let mut seen: HashMap<(String, String), (String, usize)> = HashMap::new();
+    for pack in packs {
+        let raw_monsters = match monster_loader::load_raw_monsters(pack) {
+            Ok(raw_monsters) => raw_monsters,
+            Err(e) => {
+                findings.push(Finding {
+                    file: pack.file.clone(),
+                    entry: None,
+                    monster: None,
+                    message: e.to_string(),
+                });
+                continue;
+            }
+        };
+
+        for (i, monster) in raw_monsters.iter().enumerate() {
+            let mut finding = |message: String| {
+                findings.push(Finding {
+                    file: pack.file.clone(),
+                    entry: Some(i + 1),
+                    monster: Some(monster.name.clone()),
+                    message,
+                })
+            };
+            for message in check_monster(monster) {
+                finding(message);
+            }
+
+            let key = (monster.source.clone(), monster.name.to_lowercase());
+            match seen.get(&key) {
+                Some((file, entry)) => finding(format!(
+                    "duplicate name in source '{}' (first at {}:{})",
+                    monster.source, file, entry
+                )),
+                None => {
+                    seen.insert(key, (pack.file.clone(), i + 1));
+                }
+            }
+        }
+    }
+    findings
+}
+
+fn check_monster(monster: &RawMonster) -> Vec<String> {
+    let mut messages = Vec::new();
+
+    let level = if monster.level == "*" {
+        Some(None)
+    } else {
+        match monster.level.parse::<u8>() {
+            Ok(level) => Some(Some(level)),
+            Err(_) => {
+                messages.push(format!("level '{}' is not a number", monster.level));
+                None
+            }
+        }
+    };
+
+    match StatBlock::parse(&monster.stat_block) {
+        Ok(stat_block) => {
+            if level.is_some_and(|level| level != stat_block.level) {
+                let stat_block_level = match stat_block.level {
+                    Some(level) => level.to_string(),
+                    None => "*".to_string(),
+                };
+                messages.push(format!(
+                    "level '{}' does not match the stat block's LV {}",
+                    monster.level, stat_block_level
+                ));
+            }
+            if monster.alignment != stat_block.alignment.code() {
+                messages.push(format!(
+                    "alignment '{}' does not match the stat block's AL {}",
+                    monster.alignment,
+                    stat_block.alignment.code()
+                ));
+            }
+            if !move_matches(&monster.move_amount, &stat_block) {
+                messages.push(format!(
+                    "move '{}' does not match the stat block's {}",
+                    monster.move_amount,
+                    stat_block.movement_text()
+                ));
+            }
+        }
+        Err(e) => messages.push(e.to_string()),
+    }
+
+    let mut biomes: Vec<String> = Vec::new();
+    for name in monster.biome.split(',') {
+        let normalized = biome::normalize(name);
+        if normalized.is_empty() {
+            messages.push("empty biome".to_string());
+        } else if biomes.contains(&normalized) {
+            messages.push(format!("duplicate biome '{}'", name.trim()));
+        } else if !biome::is_known(&normalized) {
+            messages.push(format!("unknown biome '{}'", name.trim()));
+        }
+        biomes.push(normalized);
+    }
+
+    let mut ability_names: Vec<String> = Vec::new();
+    for ability in monster.abilities.iter().flatten() {
+        let name = ability.name.trim();
+        if name.is_empty() {
+            messages.push("ability with no name".to_string());
+        } else if ability_names.contains(&name.to_lowercase()) {
+            messages.push(format!("duplicate ability '{}'", name));
+        }
+        if ability.description.trim().is_empty() {
+            messages.push(format!("ability '{}' has no description", name));
+        }
+        ability_names.push(name.to_lowercase());
+    }
+
+    if let Some(Err(e)) = monster.number_appearing.as_deref().map(Dice::parse) {
+        messages.push(format!("number appearing: {}", e));
+    }
+    if let Some(Err(e)) = monster.rarity.as_deref().map(Rarity::parse) {
+        messages.push(format!("rarity: {}", e));
+    }
+
+    messages
+}
+
+/// The `move` field is either empty, just the special modes (`fly`), or a copy of the
+/// stat block's MV. Whatever it does say has to agree with the stat block.
+fn move_matches(move_amount: &str, stat_block: &StatBlock) -> bool {
+    let move_amount = move_amount.trim().to_lowercase();
+    if move_amount.is_empty() {
+        return true;
+    }
+    let stated = parse_movement(&move_amount);
+    if stated.is_empty() {
+        return stat_block.movement.is_empty();
+    }
+
+    let modes = stat_block.movement_modes();
+    if stated.iter().all(|m| m.modes.is_empty() && !is_distance(&m.distance)) {
+        return stated.iter().all(|m| modes.contains(&m.distance));
+    }
+    let distances = |movement: &[Movement]| movement.iter().map(|m| m.distance.clone()).collect::<Vec<_>>();
+    let stated_modes: Vec<String> = stated.iter().flat_map(|m| m.modes.clone()).collect();
+    distances(&stated) == distances(&stat_block.movement) && (stated_modes.is_empty() || stated_modes == modes)
+}
+
+fn is_distance(text: &str) -> bool {
+    text.split(' ').any(|word| matches!(word, "close" | "near" | "far"))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::lint::lint;
+    use crate::monster_loader::Pack;
+
+    fn pack(contents: &str) -> Pack {
+        Pack {
+            file: "test.json".to_string(),
+            contents: contents.to_string(),
+        }
+    }
+
+    #[test]
+    fn clean_entry() {
+        let findings = lint(&[pack(r#"[{"name": "GOBLIN", "tags": "goblinoid", "level": "1", "biome": "cave, forest", "alignment": "C", "move": "", "attack": "", "page": "1", "source": "test",
+            "statblock": "AC 11, HP 5, ATK 1 club +0 (1d4), MV near, S +0, D +1, C +1, I -1, W -1, Ch -2, AL C, LV 1"}]"#)]);
+        assert!(findings.is_empty());
+    }
+
+    #[test]
+    fn oversized_number_appearing() {
+        let findings = lint(&[pack(r#"[{"name": "GOBLIN", "tags": "goblinoid", "level": "1", "biome": "cave", "alignment": "C", "move": "", "attack": "", "page": "1", "source": "test",
+            "statblock": "AC 11, HP 5, ATK 1 club +0 (1d4), MV near, S +0, D +1, C +1, I -1, W -1, Ch -2, AL C, LV 1", "number_appearing": "100000d100000"}]"#)]);
+        let report: Vec<String> = findings.iter().map(|f| f.to_string()).collect();
+        assert_eq!(
+            report,
+            vec!["test.json:1 (GOBLIN): number appearing: '100000d100000' rolls more than 100 dice or dice with more than 1000 sides"]
+        );
+    }
+
+    #[test]
+    fn reports_each_problem() {
+        let findings = lint(&[pack(r#"[
+            {"name": "GOBLIN", "tags": "goblinoid", "level": "2", "biome": "cave,,cave,lava", "alignment": "L", "move": "fly", "attack": "", "page": "1", "source": "test",
+             "statblock": "AC 11, HP 5, ATK 1 club +0 (1d4), MV near, S +0, D +1, C +1, I -1, W -1, Ch -2, AL C, LV 1",
+             "abilities": [{"name": "", "description": "Sneaky."}]},
+            {"name": "Goblin", "tags": "goblinoid", "level": "1", "biome": "cave", "alignment": "C", "move": "", "attack": "", "page": "1", "source": "test",
+             "statblock": "HP 5, ATK 1 club +0 (1d4), MV near, S +0, D +1, C +1, I -1, W -1, Ch -2, AL C, LV 1",
+             "number_appearing": "a few", "rarity": "legendary"}
+        ]"#)]);
+        let report: Vec<String> = findings.iter().map(|f| f.to_string()).collect();
+        assert_eq!(
+            report,
+            vec![
+                "test.json:1 (GOBLIN): level '2' does not match the stat block's LV 1",
+                "test.json:1 (GOBLIN): alignment 'L' does not match the stat block's AL C",
+                "test.json:1 (GOBLIN): move 'fly' does not match the stat block's MV near",
+                "test.json:1 (GOBLIN): empty biome",
+                "test.json:1 (GOBLIN): duplicate biome 'cave'",
+                "test.json:1 (GOBLIN): unknown biome 'lava'",
+                "test.json:1 (GOBLIN): ability with no name",
+                "test.json:2 (Goblin): stat block has no AC",
+                "test.json:2 (Goblin): number appearing: 'a few' isn't dice like 2d6+1",
+                "test.json:2 (Goblin): rarity: unknown rarity 'legendary'; try common, uncommon, rare or unique",
+                "test.json:2 (Goblin): duplicate name in source 'test' (first at test.json:1)",
+            ]
+        );
+    }
+}
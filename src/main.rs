@@ -1,209 +1,85 @@
-mod data;
-mod monster_loader;
-mod monster_wrangler;
+mod cli;
+mod menu;
 
-use crate::data::{Monster, OutputFormat};
-use crate::monster_wrangler::{Choices, MonsterWrangler};
+use crate::cli::Command;
+use randommon::lint;
+use randommon::monster_loader;
+use randommon::monster_wrangler::MonsterWrangler;
+use std::path::PathBuf;
 
 fn main() {
-    let monsters = monster_loader::get_monster_graph();
-    println!("Loaded {} monsters", monsters.len());
-
-    let wrangler = MonsterWrangler::new(monsters);
-    let mut choices = wrangler.choices();
-    let output_format = read_output_format();
-
-    loop {
-        choices = choose(&wrangler, choices, output_format);
-        let randomness = read_randomness();
-        choices = choices.with_randomness(Some(randomness));
-        println!("Choices: {}, Randomness: {}", choices.state(), randomness);
-
-        choices
-            .cluster(5, &wrangler)
-            .iter()
-            .for_each(|monster| println!("{}", monster.render(output_format)));
-    }
-}
-
-fn read_output_format() -> OutputFormat {
-    loop {
-        println!("Output format? [standard | name | html] (default standard):");
-        let mut input = String::new();
-        std::io::stdin().read_line(&mut input).unwrap();
-
-        match input.trim().to_lowercase().as_str() {
-            "" | "standard" => return OutputFormat::Standard,
-            "name" => return OutputFormat::Name,
-            "html" => return OutputFormat::Html,
-            _ => println!("Invalid output format"),
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let options = match cli::parse(&args) {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, cli::USAGE);
+            std::process::exit(2);
         }
-    }
-}
-
-fn read_randomness() -> u8 {
-    loop {
-        println!("Randomness? [1-5] (default 1):");
-        let mut input = String::new();
-        std::io::stdin().read_line(&mut input).unwrap();
-        let randomness = input.trim().parse().unwrap_or(1);
-        if (randomness < 1) || (randomness > 5) {
-            println!("Invalid randomness");
-            continue;
+    };
+    let user_packs = options.user_packs();
+    match options.command {
+        Command::Help => {
+            println!("{}", cli::USAGE);
+            return;
         }
-        return randomness;
+        Command::Lint => std::process::exit(run_lint(&user_packs)),
+        _ => {}
     }
-}
 
-fn choose(wrangler: &MonsterWrangler, choices: Choices, output_format: OutputFormat) -> Choices {
-    let mut choices = choices;
-    loop {
-        println!(
-            "\nChoose: [1:Level, 2:Biome, 3:Tag, 4: Search, 5: List, 6: Random, 7: Walk Group, g: Generate Group] (current: {}):",
-            choices.state()
-        );
-
-        let mut input = String::new();
-        std::io::stdin().read_line(&mut input).unwrap();
-        input = input.trim().to_string();
-        if input.is_empty() || input.trim() == "g" {
-            break;
+    let profiles = match options.profiles() {
+        Ok(profiles) => profiles,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
         }
-
-        let choice = input.trim().parse();
-        match choice {
-            Ok(1) => {
-                let level = choose_level(&wrangler, &choices);
-                choices = choices.with_level(level);
-            }
-            Ok(2) => {
-                let biome = choose_biome(&wrangler, &choices);
-                choices = choices.with_biome(biome);
-            }
-            Ok(3) => {
-                let tag = choose_tag(&wrangler, &choices);
-                choices = choices.with_tag(tag);
-            }
-            Ok(4) => {
-                println!("Search: ");
-                let seed_monster = search(wrangler, &choices, output_format);
-                if seed_monster.is_some() {
-                    choices = choices.with_seed_monster(seed_monster);
-                }
-            }
-            Ok(5) => {
-                let monsters = wrangler.list(&choices);
-                for monster in monsters {
-                    println!("{}", monster.render(output_format));
-                }
-            }
-            Ok(6) => {
-                let monster = wrangler.rando(&choices);
-                println!("{}", monster.render(output_format));
-
-                println!("\nWould you like to use this monster as a seed? (y/n):");
-                let mut input = String::new();
-                std::io::stdin().read_line(&mut input).unwrap();
-                if input.trim().to_lowercase() == "y" {
-                    choices = choices.with_seed_monster(Some(monster.clone()));
-                    println!("Selected seed monster: {}", monster.name);
-                }
-            }
-            Ok(7) => {
-                choices
-                    .walk(5, &wrangler)
-                    .iter()
-                    .for_each(|monster| println!("{}", monster.render(output_format)));
-            }
-            _ => {
-                println!("Invalid choice");
-            }
+    };
+    let monsters = match monster_loader::get_monster_graph(&user_packs) {
+        Ok(monsters) => monsters,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
         }
-    }
-    choices
-}
-
-fn search(
-    wrangler: &MonsterWrangler,
-    choices: &Choices,
-    output_format: OutputFormat,
-) -> Option<Monster> {
-    let mut search_term = String::new();
-    std::io::stdin().read_line(&mut search_term).unwrap();
-    let search = search_term.trim().to_string();
-    let results = wrangler.search(&choices, &search);
-
-    if results.is_empty() {
-        println!("No monsters found matching that search term.");
-        return None;
+    };
+    if options.command != Command::Interactive {
+        let wrangler = MonsterWrangler::new(monsters).with_profiles(profiles);
+        std::process::exit(cli::run(&options, &wrangler));
     }
 
-    for (i, monster) in results.iter().enumerate() {
-        println!("{}. {}", i + 1, monster.render(output_format));
-    }
-
-    println!("\nWould you like to use one of these monsters as a seed? Enter the number (or 0 to skip):");
-    let mut input = String::new();
-    std::io::stdin().read_line(&mut input).unwrap();
-    let choice = input.trim().parse::<usize>().unwrap_or(0);
-
-    if choice == 0 || choice > results.len() {
-        return None;
+    println!("Loaded {} monsters", monsters.len());
+    for pack in &user_packs {
+        let count = monsters.all().iter().filter(|m| m.file == pack.display().to_string()).count();
+        println!("  {} from {}", count, pack.display());
     }
 
-    let selected_monster = results[choice - 1].clone();
-    println!("Selected seed monster: {}", selected_monster.name);
-    Some(selected_monster)
+    menu::run(&MonsterWrangler::new(monsters).with_profiles(profiles));
 }
 
-fn choose_tag(wrangler: &&MonsterWrangler, choices: &Choices) -> String {
-    println!("tag? (default random) {:?}: ", choices.tags(&wrangler));
-    loop {
-        let mut tag = String::new();
-        std::io::stdin().read_line(&mut tag).unwrap();
-        let tag = tag.trim().to_string();
-        if choices.tags(&wrangler).contains(&tag) || tag.is_empty() {
-            return tag;
+/// Lints the built-in packs and the user packs, printing one line per finding. Returns
+/// the process exit code: 0 when everything is clean, 1 otherwise.
+fn run_lint(user_packs: &[PathBuf]) -> i32 {
+    let mut packs = monster_loader::built_in_packs();
+    let mut findings = Vec::new();
+    for path in user_packs {
+        match monster_loader::read_pack(path) {
+            Ok(pack) => packs.push(pack),
+            Err(e) => findings.push(lint::Finding {
+                file: path.display().to_string(),
+                entry: None,
+                monster: None,
+                message: e.to_string(),
+            }),
         }
-        println!("Please choose a valid tag (or none)");
     }
-}
+    findings.extend(lint::lint(&packs));
 
-fn choose_biome(wrangler: &&MonsterWrangler, choices: &Choices) -> String {
-    println!("biome? (default random) {:?}: ", choices.biomes(&wrangler));
-    loop {
-        let mut biome = String::new();
-        std::io::stdin().read_line(&mut biome).unwrap();
-        let biome = biome.trim().to_string();
-        if (choices.biomes(&wrangler).contains(&biome)) || biome.is_empty() {
-            return biome;
-        }
-        println!("Please choose a valid biome (or none)");
+    for finding in &findings {
+        println!("{}", finding);
     }
-}
-
-fn choose_level(wrangler: &&MonsterWrangler, choices: &Choices) -> Option<u8> {
-    println!(
-        "dungeon level? (default random) {:?}: ",
-        choices.levels(&wrangler)
-    );
-    loop {
-        let mut level = String::new();
-        std::io::stdin().read_line(&mut level).unwrap();
-        let level_choice = level.trim();
-        if level_choice.is_empty() {
-            return None;
-        }
-        let level = match level_choice.parse() {
-            Ok(x) => x,
-            Err(_) => {
-                println!("Level must be a number");
-                continue;
-            }
-        };
-        if (choices.levels(&wrangler).contains(&level)) || level == 0 {
-            return Some(level);
-        }
-        println!("Please choose a valid level (or none)");
+    if findings.is_empty() {
+        println!("No problems found in {} packs", packs.len());
+        0
+    } else {
+        println!("{} problems found in {} packs", findings.len(), packs.len());
+        1
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,483 @@
+use randommon::data::{Monster, OutputFormat};
+use randommon::dice::Dice;
+use randommon::encounter::{Difficulty, Party};
+use randommon::filter::{self, ValueFilter};
+use randommon::monster_wrangler::{self, Choices, MonsterWrangler};
+use randommon::sources;
+use randommon::table::{self, TableFormat};
+use randommon::walk::WalkStrategy;
+use std::ops::RangeInclusive;
+
+/// The interactive menu: pick filters, then generate groups until the program is killed.
+pub fn run(wrangler: &MonsterWrangler) {
+    let mut choices = wrangler.choices();
+    let output_format = read_output_format();
+
+    loop {
+        choices = choose(wrangler, choices, output_format);
+        let randomness = read_randomness();
+        choices = choices.with_randomness(Some(randomness));
+        let seed = choices.seed().unwrap_or_else(monster_wrangler::random_seed);
+        println!("Choices: {}, Randomness: {}, Seed: {}", choices.state(), randomness, seed);
+
+        let choices = choices.with_seed(Some(seed));
+        match choices.cluster(5, wrangler) {
+            Ok(monsters) => print_groups(&choices.counted(monsters), output_format),
+            Err(e) => println!("{}", e),
+        }
+    }
+}
+
+fn print_groups(groups: &[(Monster, u32)], output_format: OutputFormat) {
+    for (monster, count) in groups {
+        println!("{}", monster.render_count(*count, output_format));
+    }
+}
+
+fn print_monsters(monsters: &[Monster], output_format: OutputFormat) {
+    for monster in monsters {
+        println!("{}", monster.render(output_format));
+    }
+}
+
+fn read_output_format() -> OutputFormat {
+    loop {
+        println!("Output format? [standard | name | html] (default standard):");
+        let mut input = String::new();
+        std::io::stdin().read_line(&mut input).unwrap();
+
+        match input.trim().to_lowercase().as_str() {
+            "" | "standard" => return OutputFormat::Standard,
+            "name" => return OutputFormat::Name,
+            "html" => return OutputFormat::Html,
+            _ => println!("Invalid output format"),
+        }
+    }
+}
+
+fn read_randomness() -> u8 {
+    loop {
+        println!("Randomness? [1-5] (default 1):");
+        let mut input = String::new();
+        std::io::stdin().read_line(&mut input).unwrap();
+        let randomness = input.trim().parse().unwrap_or(1);
+        if !(1..=5).contains(&randomness) {
+            println!("Invalid randomness");
+            continue;
+        }
+        return randomness;
+    }
+}
+
+fn read_seed() -> Option<u64> {
+    loop {
+        println!("Seed? (default new seed every time):");
+        let mut input = String::new();
+        std::io::stdin().read_line(&mut input).unwrap();
+        let input = input.trim();
+        if input.is_empty() {
+            return None;
+        }
+        match input.parse() {
+            Ok(seed) => return Some(seed),
+            Err(_) => println!("Seed must be a number"),
+        }
+    }
+}
+
+fn choose(wrangler: &MonsterWrangler, choices: Choices, output_format: OutputFormat) -> Choices {
+    let mut choices = choices;
+    loop {
+        println!(
+            "\nChoose: [1:Level, 2:Biome, 3:Tag, 4: Search, 5: List, 6: Random, 7: Walk Group, 8: Seed, 9: Source, 10: Alignment, 11: Movement, 12: Profile, 13: Explain, 14: Path, 15: Factions, 16: Walk Strategy, 17: Encounter, 18: Numbers Appearing, 19: Encounter Table, 20: Rarity, 21: Wandering Monsters, g: Generate Group] (current: {}):",
+            choices.state()
+        );
+
+        let mut input = String::new();
+        std::io::stdin().read_line(&mut input).unwrap();
+        input = input.trim().to_string();
+        if input.is_empty() || input.trim() == "g" {
+            break;
+        }
+
+        let choice = input.trim().parse();
+        match choice {
+            Ok(1) => {
+                let levels = choose_level(&wrangler, &choices);
+                choices = choices.with_levels(levels);
+            }
+            Ok(2) => {
+                let biomes = choose_biome(&wrangler, &choices);
+                choices = choices.with_biomes(biomes);
+            }
+            Ok(3) => {
+                let tags = choose_tag(&wrangler, &choices);
+                choices = choices.with_tags(tags);
+            }
+            Ok(4) => {
+                println!("Search: ");
+                let seed_monster = search(wrangler, &choices, output_format);
+                if seed_monster.is_some() {
+                    choices = choices.with_seed_monster(seed_monster);
+                }
+            }
+            Ok(5) => {
+                let monsters = wrangler.list(&choices);
+                if monsters.is_empty() {
+                    println!("{}", wrangler.no_matches(&choices));
+                }
+                print_monsters(&monsters, output_format);
+            }
+            Ok(6) => {
+                let monster = match wrangler.rando(&choices) {
+                    Ok(monster) => monster,
+                    Err(e) => {
+                        println!("{}", e);
+                        continue;
+                    }
+                };
+                println!("{}", monster.render(output_format));
+
+                println!("\nWould you like to use this monster as a seed? (y/n):");
+                let mut input = String::new();
+                std::io::stdin().read_line(&mut input).unwrap();
+                if input.trim().to_lowercase() == "y" {
+                    choices = choices.with_seed_monster(Some(monster.clone()));
+                    println!("Selected seed monster: {}", monster.name);
+                }
+            }
+            Ok(7) => {
+                let seed = choices.seed().unwrap_or_else(monster_wrangler::random_seed);
+                println!("Seed: {}", seed);
+                let seeded = choices.with_seed(Some(seed));
+                match seeded.walk(5, wrangler) {
+                    Ok(monsters) => print_groups(&seeded.counted(monsters), output_format),
+                    Err(e) => println!("{}", e),
+                }
+            }
+            Ok(8) => {
+                choices = choices.with_seed(read_seed());
+            }
+            Ok(9) => {
+                let sources = choices.with_sources(ValueFilter::default()).sources(wrangler);
+                println!("source? (default any; a,b for either, -a to exclude)");
+                for source in &sources {
+                    println!("  {}: {}", source, sources::title(source));
+                }
+                choices = choices.with_sources(read_values(&sources, "source"));
+            }
+            Ok(10) => {
+                let alignments = choices.with_alignments(ValueFilter::default()).alignments(wrangler);
+                println!("alignment? (default any; a,b for either, -a to exclude) {:?}: ", alignments);
+                choices = choices.with_alignments(read_values(&alignments, "alignment"));
+            }
+            Ok(11) => {
+                let modes = choices.with_movement(ValueFilter::default()).movement_modes(wrangler);
+                println!("movement? (default any; a,b for either, a+b for both, -a to exclude) {:?}: ", modes);
+                choices = choices.with_movement(read_values(&modes, "movement mode"));
+            }
+            Ok(12) => {
+                choices = choices.with_profile(choose_profile(wrangler));
+            }
+            Ok(13) => explain(wrangler, &choices),
+            Ok(14) => path(wrangler, &choices, output_format),
+            Ok(15) => match wrangler.factions(&choices, 4) {
+                Ok(factions) => {
+                    for faction in factions {
+                        println!("\n== {}", faction);
+                        print_monsters(&faction.monsters, output_format);
+                    }
+                }
+                Err(e) => println!("{}", e),
+            },
+            Ok(16) => choices = choices.with_walk(choose_walk()),
+            Ok(17) => encounter(wrangler, &choices, output_format),
+            Ok(18) => {
+                println!("Roll how many of each monster turn up? (y/n):");
+                let mut input = String::new();
+                std::io::stdin().read_line(&mut input).unwrap();
+                choices = choices.with_numbers(input.trim().to_lowercase() == "y");
+            }
+            Ok(19) => table(wrangler, &choices, output_format),
+            Ok(20) => {
+                let rarities = choices.with_rarities(ValueFilter::default()).rarities(wrangler);
+                println!("rarity? (default any; a,b for either, -unique to rule out named individuals) {:?}: ", rarities);
+                choices = choices.with_rarities(read_values(&rarities, "rarity"));
+            }
+            Ok(21) => {
+                let seed = choices.seed().unwrap_or_else(monster_wrangler::random_seed);
+                println!("Seed: {}", seed);
+                match choices.with_seed(Some(seed)).wandering(wrangler) {
+                    Ok(wandering) => {
+                        println!("{}", wandering);
+                        println!("{}", wandering.monster.render_count(wandering.count, output_format));
+                    }
+                    Err(e) => println!("{}", e),
+                }
+            }
+            _ => {
+                println!("Invalid choice");
+            }
+        }
+    }
+    choices
+}
+
+fn search(
+    wrangler: &MonsterWrangler,
+    choices: &Choices,
+    output_format: OutputFormat,
+) -> Option<Monster> {
+    let mut search_term = String::new();
+    std::io::stdin().read_line(&mut search_term).unwrap();
+    let search = search_term.trim().to_string();
+    let results = wrangler.search(choices, &search);
+
+    if results.is_empty() {
+        println!("No monsters found matching that search term.");
+        return None;
+    }
+
+    for (i, monster) in results.iter().enumerate() {
+        println!("{}. {}", i + 1, monster.render(output_format));
+    }
+
+    println!("\nWould you like to use one of these monsters as a seed? Enter the number (or 0 to skip):");
+    let mut input = String::new();
+    std::io::stdin().read_line(&mut input).unwrap();
+    let choice = input.trim().parse::<usize>().unwrap_or(0);
+
+    if choice == 0 || choice > results.len() {
+        return None;
+    }
+
+    let selected_monster = results[choice - 1].clone();
+    println!("Selected seed monster: {}", selected_monster.name);
+    Some(selected_monster)
+}
+
+fn choose_tag(wrangler: &&MonsterWrangler, choices: &Choices) -> ValueFilter {
+    let tags = choices.with_tags(ValueFilter::default()).tags(wrangler);
+    println!("tag? (default random; a,b for either, a+b for both, -a to exclude) {:?}: ", tags);
+    read_values(&tags, "tag")
+}
+
+fn choose_biome(wrangler: &&MonsterWrangler, choices: &Choices) -> ValueFilter {
+    let biomes = choices.with_biomes(ValueFilter::default()).biomes(wrangler);
+    println!("biome? (default random; a,b for either, a+b for both, -a to exclude) {:?}: ", biomes);
+    read_values(&biomes, "biome")
+}
+
+/// Explains how two monsters are connected, or shows a monster's strongest and weakest
+/// links when the second name is left empty.
+fn explain(wrangler: &MonsterWrangler, choices: &Choices) {
+    let Some(from) = read_monster(wrangler, "First monster?") else {
+        return;
+    };
+    let result = match read_monster(wrangler, "Second monster? (empty for its strongest and weakest links)") {
+        Some(to) => wrangler.explain(choices, &from, &to).map(|connection| println!("{}", connection)),
+        None => wrangler.links(choices, &from, 5).map(|(strongest, weakest)| {
+            println!("Strongest links:");
+            strongest.iter().for_each(|connection| println!("{}", connection));
+            println!("Weakest links:");
+            weakest.iter().for_each(|connection| println!("{}", connection));
+        }),
+    };
+    if let Err(e) = result {
+        println!("{}", e);
+    }
+}
+
+/// Shows the chain of strongest links leading from one monster to another.
+fn path(wrangler: &MonsterWrangler, choices: &Choices, output_format: OutputFormat) {
+    let Some(from) = read_monster(wrangler, "From which monster?") else {
+        return;
+    };
+    let Some(to) = read_monster(wrangler, "To which monster?") else {
+        return;
+    };
+    match wrangler.path(choices, &from, &to) {
+        Ok(path) => print_monsters(&path, output_format),
+        Err(e) => println!("{}", e),
+    }
+}
+
+fn read_monster(wrangler: &MonsterWrangler, prompt: &str) -> Option<Monster> {
+    loop {
+        println!("{}", prompt);
+        let mut name = String::new();
+        std::io::stdin().read_line(&mut name).unwrap();
+        if name.trim().is_empty() {
+            return None;
+        }
+        match wrangler.find(&name) {
+            Some(monster) => return Some(monster),
+            None => println!("No monster named '{}'", name.trim()),
+        }
+    }
+}
+
+/// Asks about the party and difficulty, then builds an encounter from the current choices.
+fn encounter(wrangler: &MonsterWrangler, choices: &Choices, output_format: OutputFormat) {
+    let size = read_number("Party size? (default 4)", 4);
+    let level = read_number("Average character level? (default 1)", 1);
+    println!("Difficulty? (default normal) [easy, normal, hard, deadly]:");
+    let difficulty = loop {
+        let mut input = String::new();
+        std::io::stdin().read_line(&mut input).unwrap();
+        if input.trim().is_empty() {
+            break Difficulty::Normal;
+        }
+        match Difficulty::parse(input.trim()) {
+            Ok(difficulty) => break difficulty,
+            Err(e) => println!("{}", e),
+        }
+    };
+    let seed = choices.seed().unwrap_or_else(monster_wrangler::random_seed);
+    println!("Seed: {}", seed);
+    match choices.with_seed(Some(seed)).encounter(Party { size, level }, difficulty, wrangler) {
+        Ok(encounter) => {
+            println!("{}", encounter);
+            print_groups(&encounter.groups, output_format);
+        }
+        Err(e) => println!("{}", e),
+    }
+}
+
+/// Asks for the dice, prints an encounter table from the current choices, then rolls on
+/// it for as long as asked to.
+fn table(wrangler: &MonsterWrangler, choices: &Choices, output_format: OutputFormat) {
+    println!("Dice? (default d8) [d6, d8, d12, 2d6...]:");
+    let dice = loop {
+        let mut input = String::new();
+        std::io::stdin().read_line(&mut input).unwrap();
+        if input.trim().is_empty() {
+            break Dice::new(1, 8);
+        }
+        match table::parse_dice(input.trim()) {
+            Ok(dice) => break dice,
+            Err(e) => println!("{}", e),
+        }
+    };
+    let seed = choices.seed().unwrap_or_else(monster_wrangler::random_seed);
+    println!("Seed: {}", seed);
+    let encounter_table = match choices.with_seed(Some(seed)).table(dice, wrangler) {
+        Ok(encounter_table) => encounter_table,
+        Err(e) => {
+            println!("{}", e);
+            return;
+        }
+    };
+    let format = match output_format {
+        OutputFormat::Html => TableFormat::Html,
+        _ => TableFormat::Markdown,
+    };
+    print!("{}", encounter_table.render(format));
+    // Each roll takes the next seed, so rolling again gives a new result but the same
+    // seed replays the same rolls.
+    let mut roll_seed = seed;
+    loop {
+        println!("Roll on the table? (y/n):");
+        let mut input = String::new();
+        std::io::stdin().read_line(&mut input).unwrap();
+        if input.trim().to_lowercase() != "y" {
+            return;
+        }
+        let (result, entry, count) = choices.with_seed(Some(roll_seed)).roll_table(&encounter_table);
+        roll_seed = roll_seed.wrapping_add(1);
+        println!("Rolled {}", result);
+        println!("{}", entry.monster.render_count(count, output_format));
+    }
+}
+
+fn read_number(prompt: &str, default: u8) -> u8 {
+    println!("{}", prompt);
+    loop {
+        let mut input = String::new();
+        std::io::stdin().read_line(&mut input).unwrap();
+        if input.trim().is_empty() {
+            return default;
+        }
+        match input.trim().parse() {
+            Ok(n) => return n,
+            Err(_) => println!("Please enter a number"),
+        }
+    }
+}
+
+fn choose_walk() -> WalkStrategy {
+    println!("walk strategy? (default skip) [skip, weighted, softmax[:temperature], ramp, biome, new-tags]: ");
+    loop {
+        let mut input = String::new();
+        std::io::stdin().read_line(&mut input).unwrap();
+        if input.trim().is_empty() {
+            return WalkStrategy::Skip;
+        }
+        match WalkStrategy::parse(input.trim()) {
+            Ok(walk) => return walk,
+            Err(e) => println!("{}", e),
+        }
+    }
+}
+
+fn choose_profile(wrangler: &MonsterWrangler) -> Option<String> {
+    let profiles = wrangler.profiles();
+    println!("scoring profile? (default {}) {:?}: ", profiles[0], profiles);
+    loop {
+        let mut profile = String::new();
+        std::io::stdin().read_line(&mut profile).unwrap();
+        let profile = profile.trim().to_string();
+        if profile.is_empty() {
+            return None;
+        }
+        if profiles.contains(&profile) {
+            return Some(profile);
+        }
+        println!("Please choose a valid profile (or none)");
+    }
+}
+
+/// Reads a value list, insisting that every value in it is one of `valid`.
+fn read_values(valid: &[String], name: &str) -> ValueFilter {
+    loop {
+        let mut input = String::new();
+        std::io::stdin().read_line(&mut input).unwrap();
+        let values = match ValueFilter::parse(input.trim()) {
+            Ok(values) => values,
+            Err(e) => {
+                println!("{}", e);
+                continue;
+            }
+        };
+        match values.values.iter().chain(&values.excluded).find(|v| !valid.contains(v)) {
+            Some(invalid) => println!("'{}' isn't a valid {} (or leave it empty for none)", invalid, name),
+            None => return values,
+        }
+    }
+}
+
+fn choose_level(wrangler: &&MonsterWrangler, choices: &Choices) -> Option<RangeInclusive<u8>> {
+    println!(
+        "dungeon level? (default random; n-m for a range) {:?}: ",
+        choices.levels(wrangler)
+    );
+    loop {
+        let mut level = String::new();
+        std::io::stdin().read_line(&mut level).unwrap();
+        let level_choice = level.trim();
+        if level_choice.is_empty() {
+            return None;
+        }
+        let levels = match filter::parse_levels(level_choice) {
+            Ok(x) => x,
+            Err(e) => {
+                println!("{}", e);
+                continue;
+            }
+        };
+        if levels.clone().any(|level| choices.levels(wrangler).contains(&level)) || *levels.start() == 0 {
+            return Some(levels);
+        }
+        println!("Please choose a valid level (or none)");
+    }
+}
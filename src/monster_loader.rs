@@ -1,49 +1,164 @@
-use crate::data::{Ability, Monsters, Monster, RawMonster, MonsterId, StatBlock};
+use crate::biome;
+use crate::dice::Dice;
+use crate::rarity::Rarity;
+use crate::scoring;
+use crate::data::{Ability, Monsters, Monster, RawMonster, MonsterId};
+use crate::stat_block::StatBlock;
+use std::fmt;
+use std::path::{Path, PathBuf};
 
-pub fn get_monster_graph() -> Monsters {
-    Monsters::new(load_monsters())
+/// Overrides the default user pack directory.
+pub const DATA_DIR_ENV: &str = "RANDOMMON_DATA_DIR";
+
+/// The contents of one monster pack file.
+pub struct Pack {
+    pub file: String,
+    pub contents: String,
+}
+
+/// Why the monster packs couldn't be loaded.
+#[derive(Debug)]
+pub enum LoadError {
+    Read { file: String, error: std::io::Error },
+    Parse { file: String, error: serde_json::Error },
+    Monster { file: String, monster: String, message: String },
 }
 
-pub fn load_monsters() -> Vec<Monster> {
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LoadError::Read { file, error } => write!(f, "Error reading file {}: {}", file, error),
+            LoadError::Parse { file, error } => write!(f, "Error parsing file {}: {}", file, error),
+            LoadError::Monster { file, monster, message } => {
+                write!(f, "Error loading {} from {}: {}", monster, file, message)
+            }
+        }
+    }
+}
+
+/// Every pack compiled into the binary. Their sources are described in [`crate::sources`].
+const BUILT_IN_PACKS: [(&str, &str); 15] = [
+    ("core.json", include_str!("core.json")),
+    ("cs1.json", include_str!("cs1.json")),
+    ("cs2.json", include_str!("cs2.json")),
+    ("cs3.json", include_str!("cs3.json")),
+    ("cs4.json", include_str!("cs4.json")),
+    ("cs5.json", include_str!("cs5.json")),
+    ("custom.json", include_str!("custom.json")),
+    ("unnatural_selection.json", include_str!("unnatural_selection.json")),
+    ("dragontown.json", include_str!("dragontown.json")),
+    ("shadow_beasties.json", include_str!("shadow_beasties.json")),
+    ("monster_monday.json", include_str!("monster_monday.json")),
+    ("adventure_anthology.json", include_str!("adventure_anthology.json")),
+    ("gamemaster_companion.json", include_str!("gamemaster_companion.json")),
+    ("dolmenwood.json", include_str!("dolmenwood.json")),
+    ("dolmenwood-animals.json", include_str!("dolmenwood-animals.json")),
+];
+
+/// Loads the built-in packs and `user_packs` and builds the monster graph.
+pub fn get_monster_graph(user_packs: &[PathBuf]) -> Result<Monsters, LoadError> {
+    Ok(Monsters::new(load_monsters(user_packs)?))
+}
+
+/// Loads the built-in packs followed by `user_packs`.
+pub fn load_monsters(user_packs: &[PathBuf]) -> Result<Vec<Monster>, LoadError> {
+    let mut packs = built_in_packs();
+    for path in user_packs {
+        packs.push(read_pack(path)?);
+    }
+    load_packs(&packs)
+}
+
+/// Loads the monsters of `packs`, in order, without the built-in packs.
+pub fn load_packs(packs: &[Pack]) -> Result<Vec<Monster>, LoadError> {
     let mut raw_monsters = Vec::new();
-    let files = vec![
-        include_str!("core.json"),
-        include_str!("cs1.json"),
-        include_str!("cs2.json"),
-        include_str!("cs3.json"),
-        include_str!("cs4.json"),
-        include_str!("cs5.json"),
-        include_str!("custom.json"),
-        include_str!("unnatural_selection.json"),
-        include_str!("dragontown.json"),
-        include_str!("shadow_beasties.json"),
-        include_str!("monster_monday.json"),
-        include_str!("adventure_anthology.json"),
-        include_str!("gamemaster_companion.json"),
-        include_str!("dolmenwood.json"),
-    ];
-    for file in files {
-        raw_monsters.append(&mut load_raw_monsters(file));
+    for pack in packs {
+        for raw_monster in load_raw_monsters(pack)? {
+            raw_monsters.push((pack.file.clone(), raw_monster));
+        }
     }
 
     convert_to_monsters(raw_monsters)
 }
 
-fn load_raw_monsters(file: &str) -> Vec<RawMonster> {
-    let raw_monsters = match serde_json::from_str::<Vec<RawMonster>>(file) {
-        Ok(monsters) => monsters,
-        Err(e) => {
-            eprintln!("Error parsing file {}: {}", file, e);
-            std::process::exit(1);
-        }
+/// The packs compiled into the binary.
+pub fn built_in_packs() -> Vec<Pack> {
+    BUILT_IN_PACKS
+        .iter()
+        .map(|(file, contents)| Pack {
+            file: file.to_string(),
+            contents: contents.to_string(),
+        })
+        .collect()
+}
+
+/// The directory searched for user packs: `$RANDOMMON_DATA_DIR` if set, otherwise
+/// `~/.config/randommon`.
+pub fn default_data_dir() -> Option<PathBuf> {
+    if let Some(dir) = std::env::var_os(DATA_DIR_ENV) {
+        return Some(PathBuf::from(dir));
+    }
+    std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config").join("randommon"))
+}
+
+/// Every `*.json` file directly inside `data_dir` except the scoring profiles, sorted by
+/// file name. A missing directory simply contributes no packs.
+pub fn discover_packs(data_dir: &Path) -> Vec<PathBuf> {
+    let entries = match std::fs::read_dir(data_dir) {
+        Ok(entries) => entries,
+        Err(_) => return Vec::new(),
     };
-    raw_monsters
+    let mut paths: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.is_file() && path.extension().is_some_and(|ext| ext == "json"))
+        .filter(|path| path.file_name().is_some_and(|name| name != scoring::PROFILES_FILE))
+        .collect();
+    paths.sort();
+    paths
+}
+
+/// Reads a user pack from disk.
+pub fn read_pack(path: &Path) -> Result<Pack, LoadError> {
+    let file = path.display().to_string();
+    match std::fs::read_to_string(path) {
+        Ok(contents) => Ok(Pack { file, contents }),
+        Err(error) => Err(LoadError::Read { file, error }),
+    }
+}
+
+/// Parses a pack's JSON without checking the entries.
+pub fn load_raw_monsters(pack: &Pack) -> Result<Vec<RawMonster>, LoadError> {
+    serde_json::from_str::<Vec<RawMonster>>(&pack.contents).map_err(|error| LoadError::Parse {
+        file: pack.file.clone(),
+        error,
+    })
 }
 
-fn convert_to_monsters(raw_monsters: Vec<RawMonster>) -> Vec<Monster> {
+/// How many of a monster usually turn up when its pack doesn't say: one of anything
+/// legendary, swarming or level 7 and up, bands of low-level humanoids and packs of
+/// small animals, and a few of anything else.
+fn default_number_appearing(monster: &Monster) -> Dice {
+    let tagged = |tag: &str| monster.tags.iter().any(|t| t.contains(tag));
+    match monster.level {
+        _ if tagged("legendary") || tagged("swarm") || monster.name.contains("SWARM") => Dice::fixed(1),
+        7.. => Dice::fixed(1),
+        0..=2 if tagged("humanoid") => Dice::new(2, 4),
+        0..=2 if tagged("animal") || tagged("beast") || tagged("vermin") || tagged("insect") => Dice::new(1, 6),
+        0..=2 => Dice::new(1, 4),
+        3..=4 => Dice::new(1, 4),
+        _ => Dice::new(1, 2),
+    }
+}
+
+fn convert_to_monsters(raw_monsters: Vec<(String, RawMonster)>) -> Result<Vec<Monster>, LoadError> {
     let mut id : MonsterId = 0;
-    let monsters = raw_monsters.iter().map(|monster| {
-        let monster = Monster {
+    let monsters = raw_monsters.iter().map(|(file, monster)| {
+        let error = |message: String| LoadError::Monster {
+            file: file.clone(),
+            monster: monster.name.clone(),
+            message,
+        };
+        let mut converted = Monster {
             id,
             name: monster.name.clone(),
             tags: monster
@@ -54,21 +169,30 @@ fn convert_to_monsters(raw_monsters: Vec<RawMonster>) -> Vec<Monster> {
             level: if monster.level == "*" {
                 10
             } else {
-                monster.level.parse().unwrap()
+                monster
+                    .level
+                    .parse()
+                    .map_err(|_| error(format!("invalid level '{}'", monster.level)))?
             },
             biomes: monster
                 .biome
                 .split(',')
-                .map(|s| s.trim().to_string())
+                .map(biome::normalize)
                 .filter(|b| !b.is_empty())
-                .collect(),
+                .fold(Vec::new(), |mut biomes, b| {
+                    if !biomes.contains(&b) {
+                        biomes.push(b);
+                    }
+                    biomes
+                }),
             alignment: monster.alignment.clone(),
             move_amount: monster.move_amount.clone(),
             attack: monster.attack.clone(),
             page: monster.page.clone(),
             raw_stat_block: monster.stat_block.clone(),
             source: monster.source.clone(),
-            stat_block: StatBlock::parse(&monster.stat_block),
+            file: file.clone(),
+            stat_block: StatBlock::parse(&monster.stat_block).map_err(|e| error(e.to_string()))?,
             description: monster.description.clone(),
             abilities: monster.abilities.as_ref().map(|abilities| {
                 abilities.iter().map(|a| Ability {
@@ -76,9 +200,19 @@ fn convert_to_monsters(raw_monsters: Vec<RawMonster>) -> Vec<Monster> {
                     description: a.description.clone(),
                 }).collect()
             }),
+            number_appearing: Dice::fixed(1),
+            rarity: Rarity::Common,
+        };
+        converted.number_appearing = match &monster.number_appearing {
+            Some(dice) => Dice::parse(dice).map_err(error)?,
+            None => default_number_appearing(&converted),
+        };
+        converted.rarity = match &monster.rarity {
+            Some(rarity) => Rarity::parse(rarity).map_err(error)?,
+            None => Rarity::infer(&converted.tags),
         };
-        id = id + 1;
-        monster
+        id += 1;
+        Ok(converted)
     });
-    return monsters.collect();
+    monsters.collect()
 }
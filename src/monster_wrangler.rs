@@ -1,28 +1,171 @@
-use crate::data::{Monster, Monsters};
+use crate::biome;
+use crate::data::{Connection, Monster, Monsters, View};
+use crate::dice::Dice;
+use crate::encounter::{Difficulty, Encounter, Party};
+use crate::export::{self, GraphFormat};
+use crate::faction::Faction;
+use crate::filter::{self, ValueFilter};
+use crate::scoring::{self, ScoringProfile};
+use crate::sources;
+use crate::table::{EncounterTable, TableEntry};
+use crate::walk::WalkStrategy;
+use crate::wandering::Wandering;
 use rand::prelude::*;
+use rand::rngs::StdRng;
+use std::collections::HashMap;
+use std::fmt;
+use std::ops::RangeInclusive;
+use std::sync::{Arc, Mutex};
 
+/// Answers questions about a [`Monsters`] graph for a given set of [`Choices`].
 pub struct MonsterWrangler {
-    monsters: Monsters,
+    monsters: Arc<Monsters>,
+    profiles: Vec<ScoringProfile>,
+    /// Graphs rescored for profiles other than the one `monsters` was built with, built
+    /// the first time each profile is asked for.
+    graphs: Mutex<HashMap<String, Arc<Monsters>>>,
 }
 
 impl MonsterWrangler {
     pub fn new(monsters: Monsters) -> MonsterWrangler {
-        MonsterWrangler { monsters }
+        MonsterWrangler {
+            monsters: Arc::new(monsters),
+            profiles: scoring::built_in_profiles(),
+            graphs: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Replaces the scoring profiles [`Choices::with_profile`] can pick from.
+    pub fn with_profiles(self, profiles: Vec<ScoringProfile>) -> MonsterWrangler {
+        MonsterWrangler { profiles, ..self }
+    }
+
+    /// The names of the scoring profiles, in the order they were given.
+    pub fn profiles(&self) -> Vec<String> {
+        self.profiles.iter().map(|profile| profile.name.clone()).collect()
+    }
+
+    /// The graph scored by the named profile, or the loaded graph for `None`.
+    fn graph(&self, profile: Option<&str>) -> Result<Arc<Monsters>, PickError> {
+        let name = match profile {
+            Some(name) if name != self.monsters.profile().name => name,
+            _ => return Ok(self.monsters.clone()),
+        };
+        if let Some(graph) = self.graphs.lock().unwrap().get(name) {
+            return Ok(graph.clone());
+        }
+        let profile = self
+            .profiles
+            .iter()
+            .find(|profile| profile.name == name)
+            .ok_or_else(|| PickError::UnknownProfile {
+                name: name.to_string(),
+                known: self.profiles(),
+            })?;
+        let graph = Arc::new(self.monsters.rescore(profile.clone()));
+        self.graphs.lock().unwrap().insert(name.to_string(), graph.clone());
+        Ok(graph)
     }
 
+    /// Choices matching every monster from the sources that are enabled by default.
     pub fn choices(&self) -> Choices {
         Choices::default()
     }
 
+    /// Every monster matching the choices, in source order.
     pub fn list(&self, choices: &Choices) -> Vec<Monster> {
         choices.apply_filters(&self.monsters)
     }
 
-    pub fn rando(&self, choices: &Choices) -> Monster {
+    /// A random monster matching the choices.
+    pub fn rando(&self, choices: &Choices) -> Result<Monster, PickError> {
         choices.rando(&self.monsters)
     }
 
-    pub fn search(&self, choices: &Choices, search_term: &String) -> Vec<Monster> {
+    /// Explains why the choices match no monsters, for callers that got an empty list.
+    pub fn no_matches(&self, choices: &Choices) -> PickError {
+        choices.no_matches(&self.monsters)
+    }
+
+    /// The monster with exactly this name, ignoring case. When several sources share a
+    /// name the one loaded first wins.
+    pub fn find(&self, name: &str) -> Option<Monster> {
+        self.monsters
+            .all()
+            .into_iter()
+            .filter(|monster| monster.name.eq_ignore_ascii_case(name.trim()))
+            .min_by_key(|monster| monster.id)
+            .cloned()
+    }
+
+    /// Why `from` and `to` are related, scored by the choices' profile.
+    pub fn explain(&self, choices: &Choices, from: &Monster, to: &Monster) -> Result<Connection, PickError> {
+        Ok(self.graph(choices.profile.as_deref())?.explain(from, to))
+    }
+
+    /// The `count` strongest and weakest links from `monster` to monsters matching the
+    /// choices, scored by the choices' profile.
+    pub fn links(
+        &self,
+        choices: &Choices,
+        monster: &Monster,
+        count: usize,
+    ) -> Result<(Vec<Connection>, Vec<Connection>), PickError> {
+        let graph = self.graph(choices.profile.as_deref())?;
+        Ok(choices.view(&graph).links(monster, count))
+    }
+
+    /// The chain of most strongly related monsters leading from `from` to `to`, both
+    /// included, through monsters matching the choices.
+    pub fn path(&self, choices: &Choices, from: &Monster, to: &Monster) -> Result<Vec<Monster>, PickError> {
+        let graph = self.graph(choices.profile.as_deref())?;
+        match choices.view(&graph).path(from, to) {
+            Some(path) => Ok(path.into_iter().cloned().collect()),
+            None => Err(PickError::NoPath {
+                from: from.name.clone(),
+                to: to.name.clone(),
+                filters: choices.filters(),
+            }),
+        }
+    }
+
+    /// Up to `count` factions among the monsters matching the choices, largest first.
+    /// Monsters with no close relatives among them belong to no faction.
+    pub fn factions(&self, choices: &Choices, count: usize) -> Result<Vec<Faction>, PickError> {
+        // How many of each monster's strongest links decide which faction it joins.
+        const FACTION_LINKS: usize = 6;
+        let graph = self.graph(choices.profile.as_deref())?;
+        let view = choices.view(&graph);
+        let factions: Vec<Faction> = view
+            .communities(FACTION_LINKS)
+            .into_iter()
+            .filter(|community| community.len() > 1)
+            .take(count)
+            .filter_map(|community| Faction::new(community.into_iter().cloned().collect()))
+            .collect();
+        if factions.is_empty() && self.list(choices).is_empty() {
+            return Err(self.no_matches(choices));
+        }
+        if factions.is_empty() {
+            return Err(PickError::NoFactions {
+                filters: choices.filters(),
+            });
+        }
+        Ok(factions)
+    }
+
+    /// The graph of monsters matching the choices, scored by the choices' profile, with
+    /// only the links of at least `min_strength`.
+    pub fn export(&self, choices: &Choices, min_strength: i32, format: GraphFormat) -> Result<String, PickError> {
+        if self.list(choices).is_empty() {
+            return Err(self.no_matches(choices));
+        }
+        let graph = self.graph(choices.profile.as_deref())?;
+        Ok(export::export(&choices.view(&graph), min_strength, format))
+    }
+
+    /// Monsters matching the choices whose name, tags or biomes contain `search_term`.
+    pub fn search(&self, choices: &Choices, search_term: &str) -> Vec<Monster> {
         let search_term = search_term.to_lowercase();
         choices
             .apply_filters(&self.monsters)
@@ -38,168 +181,583 @@ impl MonsterWrangler {
                         .iter()
                         .any(|biome| biome.to_lowercase().contains(&search_term))
             })
-            .map(|monster| monster.clone())
+            .cloned()
             .collect()
     }
 }
 
-#[derive(Default)]
+/// Why monsters couldn't be picked for a set of [`Choices`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum PickError {
+    /// No monster passes every filter. `suggestions` names each filter that would find
+    /// something if it were dropped on its own, with how many monsters it would find.
+    NoMatches { filters: String, suggestions: Vec<(String, usize)> },
+    /// The seed monster has no relatives among the monsters passing the filters.
+    NoNeighbors { monster: String, filters: String },
+    /// No chain of related monsters passing the filters joins the two monsters.
+    NoPath { from: String, to: String, filters: String },
+    /// None of the monsters passing the filters are related closely enough to form a faction.
+    NoFactions { filters: String },
+    /// There's no scoring profile with this name.
+    UnknownProfile { name: String, known: Vec<String> },
+}
+
+impl fmt::Display for PickError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PickError::NoMatches { filters, suggestions } => {
+                write!(f, "No monsters match {}", filters)?;
+                if suggestions.is_empty() {
+                    return write!(f, "; try clearing more than one filter");
+                }
+                let suggestions: Vec<String> = suggestions
+                    .iter()
+                    .map(|(filter, count)| format!("{} ({} monsters)", filter, count))
+                    .collect();
+                write!(f, "; try dropping {}", suggestions.join(" or "))
+            }
+            PickError::NoNeighbors { monster, filters } => {
+                write!(f, "{} has no relatives matching {}; try relaxing a filter", monster, filters)
+            }
+            PickError::NoPath { from, to, filters } => {
+                write!(f, "Nothing matching {} leads from {} to {}; try relaxing a filter", filters, from, to)
+            }
+            PickError::NoFactions { filters } => {
+                write!(f, "The monsters matching {} are too few or too unrelated to form factions", filters)
+            }
+            PickError::UnknownProfile { name, known } => {
+                write!(f, "No scoring profile named '{}'; try one of {}", name, known.join(", "))
+            }
+        }
+    }
+}
+
+/// A fresh seed for [`Choices::with_seed`], so that a group generated without one can
+/// still be reproduced later.
+pub fn random_seed() -> u64 {
+    thread_rng().gen()
+}
+
+/// What a generator from one seed is used for. Each gets its own sequence, so that
+/// numbers appearing and hit points aren't the rolls that picked the monsters again.
+#[derive(Clone, Copy)]
+enum Stream {
+    Pick,
+    Numbers,
+    HitPoints,
+    Table,
+}
+
+/// The filters and settings used to pick monsters. Choices are immutable; each `with_`
+/// method returns an updated copy.
+#[derive(Default, Clone)]
 pub struct Choices {
-    level: Option<u8>,
-    biome: Option<String>,
-    tag: Option<String>,
+    levels: Option<RangeInclusive<u8>>,
+    biomes: ValueFilter,
+    tags: ValueFilter,
+    sources: ValueFilter,
+    alignments: ValueFilter,
+    movement: ValueFilter,
+    rarities: ValueFilter,
     randomness: Option<u8>,
+    walk: WalkStrategy,
+    numbers: bool,
     seed_monster: Option<Monster>,
+    seed: Option<u64>,
+    profile: Option<String>,
 }
 
 impl Choices {
-    pub fn cluster(&self, number: i32, monster_wrangler: &MonsterWrangler) -> Vec<Monster> {
+    /// The seed monster (or a random one) followed by `number` of its closest relatives.
+    /// Higher randomness shuffles the group and reaches further from the seed.
+    pub fn cluster(&self, number: i32, monster_wrangler: &MonsterWrangler) -> Result<Vec<Monster>, PickError> {
+        let graph = monster_wrangler.graph(self.profile.as_deref())?;
+        let mut rng = self.rng(Stream::Pick);
         // Use the seed monster if provided, otherwise get a random one
         let seed_monster = match &self.seed_monster {
             Some(monster) => monster.clone(),
-            None => self.rando(&monster_wrangler.monsters),
+            None => self.pick(&graph, &mut rng)?,
         };
 
-        let randomness = self.randomness.unwrap();
+        let randomness = self.randomness.unwrap_or(1);
         let size = randomness as i32 * number;
-        let filtered_monsters = Monsters::new(self.apply_filters(&monster_wrangler.monsters));
-        let adjacent = filtered_monsters.get_adjacent(&seed_monster, size as u32);
-        let mut result = vec![seed_monster];
+        let filtered_monsters = self.view(&graph);
+        let mut adjacent = filtered_monsters.get_adjacent(&seed_monster, size as u32);
+        if adjacent.is_empty() && number > 0 {
+            return Err(self.no_neighbors(&seed_monster));
+        }
         if randomness > 1 {
-            result.shuffle(&mut thread_rng());
+            adjacent.shuffle(&mut rng);
         }
+        let mut result = vec![seed_monster];
         for i in 0..number.min(adjacent.len() as i32) {
             result.push(adjacent[i as usize].clone());
         }
-        result
+        Ok(result)
+    }
+
+    /// An encounter for `party` built from the seed monster (or a random one) and its
+    /// closest relatives: a leader and groups of weaker followers whose levels add up
+    /// to the difficulty's budget. Higher randomness shuffles who is considered.
+    pub fn encounter(
+        &self,
+        party: Party,
+        difficulty: Difficulty,
+        monster_wrangler: &MonsterWrangler,
+    ) -> Result<Encounter, PickError> {
+        // How many of the seed's relatives may join the encounter.
+        const CANDIDATES: u32 = 10;
+        let graph = monster_wrangler.graph(self.profile.as_deref())?;
+        let mut rng = self.rng(Stream::Pick);
+        let seed_monster = match &self.seed_monster {
+            Some(monster) => monster.clone(),
+            None => self.pick(&graph, &mut rng)?,
+        };
+
+        let randomness = self.randomness.unwrap_or(1);
+        let filtered_monsters = self.view(&graph);
+        let mut adjacent = filtered_monsters.get_adjacent(&seed_monster, randomness as u32 * CANDIDATES);
+        if randomness > 1 {
+            adjacent.shuffle(&mut rng);
+        }
+        let mut candidates = vec![seed_monster];
+        candidates.extend(adjacent.into_iter().take(CANDIDATES as usize).cloned());
+        Ok(Encounter::build(&candidates, party, difficulty).expect("the seed monster is always a candidate"))
+    }
+
+    /// A random encounter table for `dice` drawn from the monsters passing the filters,
+    /// favouring common ones. The commonest and lowest-level monsters go on the likeliest
+    /// results and the rarest and highest-level on the extremes.
+    pub fn table(&self, dice: Dice, monster_wrangler: &MonsterWrangler) -> Result<EncounterTable, PickError> {
+        let filtered = monster_wrangler.list(self);
+        let mut monsters: Vec<Monster> = filtered
+            .choose_multiple_weighted(&mut self.rng(Stream::Pick), EncounterTable::size(dice), |m| m.rarity.weight())
+            .expect("rarity weights are positive")
+            .cloned()
+            .collect();
+        monsters.sort_by_key(|monster| (monster.rarity, monster.level, monster.id));
+        EncounterTable::build(dice, &monsters).ok_or_else(|| monster_wrangler.no_matches(self))
     }
 
-    pub fn walk(&self, number: i32, monster_wrangler: &MonsterWrangler) -> Vec<Monster> {
+    /// Rolls once on `table`, so that the same seed gives the same roll as well as the
+    /// same table.
+    pub fn roll_table<'a>(&self, table: &'a EncounterTable) -> (i32, &'a TableEntry, u32) {
+        table.roll(&mut self.rng(Stream::Table))
+    }
+
+    /// A wandering monster group: the seed monster (or a random one, favouring common
+    /// monsters) with how many turn up, what they're doing, how far away they are, who
+    /// is surprised, how they react and whether they'll flee.
+    pub fn wandering(&self, monster_wrangler: &MonsterWrangler) -> Result<Wandering, PickError> {
+        let mut rng = self.rng(Stream::Pick);
+        let monster = match &self.seed_monster {
+            Some(monster) => monster.clone(),
+            None => self.pick(&monster_wrangler.monsters, &mut rng)?,
+        };
+        Ok(Wandering::roll(&monster, &mut rng))
+    }
+
+    /// Each monster of a cluster or walk with how many of it there are: a roll of its
+    /// number appearing when numbers are on, otherwise one.
+    pub fn counted(&self, monsters: Vec<Monster>) -> Vec<(Monster, u32)> {
+        let mut rng = self.rng(Stream::Numbers);
+        monsters
+            .into_iter()
+            .map(|monster| {
+                let count = if self.numbers {
+                    monster.number_appearing.roll(&mut rng).max(1) as u32
+                } else {
+                    1
+                };
+                (monster, count)
+            })
+            .collect()
+    }
+
+    /// Fresh hit points for every individual in each group, in the same order.
+    pub fn roll_hit_points(&self, groups: &[(Monster, u32)]) -> Vec<Vec<u16>> {
+        let mut rng = self.rng(Stream::HitPoints);
+        groups
+            .iter()
+            .map(|(monster, count)| (0..*count).map(|_| monster.roll_hit_points(&mut rng)).collect())
+            .collect()
+    }
+
+    /// A chain of `number` monsters, each one related to the one before, starting from
+    /// the seed monster (or a random one), each step chosen by the walk strategy. The
+    /// chain stops early if the strategy runs out of monsters to step to.
+    pub fn walk(&self, number: i32, monster_wrangler: &MonsterWrangler) -> Result<Vec<Monster>, PickError> {
+        let graph = monster_wrangler.graph(self.profile.as_deref())?;
+        let mut rng = self.rng(Stream::Pick);
         // Use the seed monster if provided, otherwise get a random one
         let seed_monster = match &self.seed_monster {
             Some(monster) => monster.clone(),
-            None => self.rando(&monster_wrangler.monsters),
+            None => self.pick(&graph, &mut rng)?,
         };
 
-        let mut cur_monster = seed_monster.clone();
         let mut result = vec![seed_monster];
-        let filtered_monsters = Monsters::new(self.apply_filters(&monster_wrangler.monsters));
+        let filtered_monsters = self.view(&graph);
         for _ in 0..number {
-            let randomness = &thread_rng().gen_range(1..10);
-            cur_monster = filtered_monsters
-                .get_neighbor_excluding(&cur_monster, &result, randomness)
-                .clone();
-            result.push(cur_monster.clone());
+            match self.walk.step(&filtered_monsters, &result, &mut rng) {
+                Some(monster) => result.push(monster.clone()),
+                None => break,
+            }
+        }
+        if result.len() == 1 && number > 0 {
+            return Err(self.no_neighbors(&result[0]));
         }
-        result
+        Ok(result)
+    }
+
+    pub fn rando(&self, monsters: &Monsters) -> Result<Monster, PickError> {
+        self.pick(monsters, &mut self.rng(Stream::Pick))
     }
 
-    pub fn rando(&self, monsters: &Monsters) -> Monster {
+    fn pick(&self, monsters: &Monsters, rng: &mut StdRng) -> Result<Monster, PickError> {
         let filtered = self.apply_filters(monsters);
-        let mut rng = thread_rng();
-        let index = rng.gen_range(0..filtered.len());
-        filtered[index].clone()
+        if filtered.is_empty() {
+            return Err(self.no_matches(monsters));
+        }
+        Ok(filtered
+            .choose_weighted(rng, |monster| monster.rarity.weight())
+            .expect("rarity weights are positive")
+            .clone())
+    }
+
+    /// The error for filters that match nothing, suggesting each filter whose removal
+    /// alone would bring some monsters back.
+    fn no_matches(&self, monsters: &Monsters) -> PickError {
+        let mut relaxed = Vec::new();
+        if let Some(levels) = &self.levels {
+            relaxed.push((format!("level={}", filter::format_levels(levels)), self.with_levels(None)));
+        }
+        if !self.biomes.is_empty() {
+            relaxed.push((format!("biome={}", self.biomes), self.with_biomes(ValueFilter::default())));
+        }
+        if !self.tags.is_empty() {
+            relaxed.push((format!("tag={}", self.tags), self.with_tags(ValueFilter::default())));
+        }
+        if !self.sources.is_empty() {
+            relaxed.push((format!("source={}", self.sources), self.with_sources(ValueFilter::default())));
+        }
+        if !self.alignments.is_empty() {
+            relaxed.push((
+                format!("alignment={}", self.alignments),
+                self.with_alignments(ValueFilter::default()),
+            ));
+        }
+        if !self.movement.is_empty() {
+            relaxed.push((format!("move={}", self.movement), self.with_movement(ValueFilter::default())));
+        }
+        if !self.rarities.is_empty() {
+            relaxed.push((format!("rarity={}", self.rarities), self.with_rarities(ValueFilter::default())));
+        }
+        let suggestions = relaxed
+            .into_iter()
+            .map(|(filter, choices)| (filter, choices.apply_filters(monsters).len()))
+            .filter(|(_, count)| *count > 0)
+            .collect();
+        PickError::NoMatches {
+            filters: self.filters(),
+            suggestions,
+        }
+    }
+
+    /// Just the filters from [`Choices::state`], without the seed, seed monster or profile.
+    fn filters(&self) -> String {
+        self.with_seed(None)
+            .with_seed_monster(None)
+            .with_profile(None)
+            .with_walk(WalkStrategy::Skip)
+            .with_numbers(false)
+            .state()
+    }
+
+    fn no_neighbors(&self, monster: &Monster) -> PickError {
+        PickError::NoNeighbors {
+            monster: monster.name.clone(),
+            filters: self.filters(),
+        }
+    }
+
+    /// A generator for `stream` seeded from the choices' seed, or from entropy when there
+    /// is none.
+    fn rng(&self, stream: Stream) -> StdRng {
+        match self.seed {
+            Some(seed) => StdRng::seed_from_u64(seed ^ (stream as u64).wrapping_mul(0x9e37_79b9_7f4a_7c15)),
+            None => StdRng::from_entropy(),
+        }
+    }
+
+    pub fn seed(&self) -> Option<u64> {
+        self.seed
     }
 
     fn apply_filters(&self, monsters: &Monsters) -> Vec<Monster> {
-        monsters
+        let mut filtered: Vec<Monster> = monsters
             .all()
-            .iter()
-            .filter(|&&monster| match &self.biome {
-                None => true,
-                Some(biome) => {
-                    monster.biomes.contains(biome) || monster.biomes.contains(&"*".to_string())
-                }
-            })
-            .filter(|&&monster| match &self.level {
-                None => true,
-                Some(level) => monster.level == *level,
-            })
-            .filter(|&&monster| match &self.tag {
-                None => true,
-                Some(tag) => monster.tags.contains(tag),
-            })
-            .map(|&monster| monster.clone())
-            .collect()
+            .into_iter()
+            .filter(|monster| self.matches(monster))
+            .cloned()
+            .collect();
+        filtered.sort_by_key(|monster| (sources::order(&monster.source), monster.id));
+        filtered
+    }
+
+    /// The part of the graph that passes the filters.
+    fn view<'a>(&self, monsters: &'a Monsters) -> View<'a> {
+        let visible = monsters
+            .all()
+            .into_iter()
+            .filter(|monster| self.matches(monster))
+            .map(|monster| monster.id)
+            .collect();
+        monsters.view(Some(visible))
+    }
+
+    fn matches(&self, monster: &Monster) -> bool {
+        let level = match &self.levels {
+            None => true,
+            Some(levels) => levels.contains(&monster.level),
+        };
+        // Asking for a biome takes in the biomes inside or around it; ruling one out
+        // only drops monsters that live in it by name.
+        let biome = self.biomes.matches(
+            |b| biome::matches(&monster.biomes, b),
+            |b| monster.biomes.iter().any(|mb| mb == b),
+        );
+        let has_tag = |t: &str| monster.tags.iter().any(|tag| tag == t);
+        let tag = self.tags.matches(has_tag, has_tag);
+        let has_source = |s: &str| monster.source.eq_ignore_ascii_case(s);
+        // Sources disabled by default only match when asked for by name.
+        let enabled = sources::enabled(&monster.source) || self.sources.values.iter().any(|s| has_source(s));
+        let source = enabled && self.sources.matches(has_source, has_source);
+        let has_alignment = |a: &str| monster.alignment == a;
+        let alignment = self.alignments.matches(has_alignment, has_alignment);
+        let modes = monster.stat_block.movement_modes();
+        let has_mode = |m: &str| modes.iter().any(|mode| mode == m);
+        let movement = self.movement.matches(has_mode, has_mode);
+        let has_rarity = |r: &str| monster.rarity.to_string() == r;
+        let rarity = self.rarities.matches(has_rarity, has_rarity);
+        level && biome && tag && source && alignment && movement && rarity
     }
 
     pub fn with_seed_monster(&self, seed_monster: Option<Monster>) -> Choices {
         Choices {
-            level: self.level,
-            biome: self.biome.clone(),
-            tag: self.tag.clone(),
-            randomness: self.randomness,
             seed_monster,
+            ..self.clone()
         }
     }
 
+    /// Only monsters found in `biome`, or any biome if it is empty.
     pub fn with_biome(&self, biome: String) -> Choices {
+        self.with_biomes(ValueFilter::single(&biome))
+    }
+
+    /// Biome names are normalized, so aliases such as `coastal` work.
+    pub fn with_biomes(&self, biomes: ValueFilter) -> Choices {
         Choices {
-            level: self.level,
-            biome: if biome.is_empty() { None } else { Some(biome) },
-            tag: self.tag.clone(),
-            randomness: self.randomness,
-            seed_monster: self.seed_monster.clone(),
+            biomes: biomes.map(biome::normalize),
+            ..self.clone()
         }
     }
 
+    /// Only monsters with `tag`, or any tag if it is empty.
     pub fn with_tag(&self, tag: String) -> Choices {
+        self.with_tags(ValueFilter::single(&tag))
+    }
+
+    pub fn with_tags(&self, tags: ValueFilter) -> Choices {
+        Choices { tags, ..self.clone() }
+    }
+
+    /// Only monsters from `source`, or any source if it is empty.
+    pub fn with_source(&self, source: String) -> Choices {
+        self.with_sources(ValueFilter::single(&source))
+    }
+
+    /// Sources to allow and to rule out, ignoring case.
+    pub fn with_sources(&self, sources: ValueFilter) -> Choices {
+        Choices { sources, ..self.clone() }
+    }
+
+    /// Alignment codes (`L`, `N`, `C`) in either case.
+    pub fn with_alignments(&self, alignments: ValueFilter) -> Choices {
         Choices {
-            level: self.level,
-            biome: self.biome.clone(),
-            tag: if tag.is_empty() { None } else { Some(tag) },
-            randomness: self.randomness,
-            seed_monster: self.seed_monster.clone(),
+            alignments: alignments.map(str::to_uppercase),
+            ..self.clone()
         }
     }
 
-    pub fn with_level(&self, level: Option<u8>) -> Choices {
+    /// Movement modes from the stat block's MV, such as `fly` or `swim`.
+    pub fn with_movement(&self, movement: ValueFilter) -> Choices {
+        Choices {
+            movement: movement.map(str::to_lowercase),
+            ..self.clone()
+        }
+    }
+
+    /// Rarities (`common`, `uncommon`, `rare`, `unique`) in either case; `-unique` rules
+    /// out named individuals.
+    pub fn with_rarities(&self, rarities: ValueFilter) -> Choices {
         Choices {
-            level,
-            biome: self.biome.clone(),
-            tag: self.tag.clone(),
-            randomness: self.randomness,
-            seed_monster: self.seed_monster.clone(),
+            rarities: rarities.map(str::to_lowercase),
+            ..self.clone()
         }
     }
 
+    /// Only monsters of exactly this level.
+    pub fn with_level(&self, level: Option<u8>) -> Choices {
+        self.with_levels(level.map(|level| level..=level))
+    }
+
+    pub fn with_levels(&self, levels: Option<RangeInclusive<u8>>) -> Choices {
+        Choices { levels, ..self.clone() }
+    }
+
     pub fn with_randomness(&self, randomness: Option<u8>) -> Choices {
         Choices {
-            level: self.level,
-            biome: self.biome.clone(),
-            tag: self.tag.clone(),
             randomness,
-            seed_monster: self.seed_monster.clone(),
+            ..self.clone()
+        }
+    }
+
+    /// Whether [`Choices::counted`] rolls how many of each monster turn up.
+    pub fn with_numbers(&self, numbers: bool) -> Choices {
+        Choices {
+            numbers,
+            ..self.clone()
+        }
+    }
+
+    /// How [`Choices::walk`] picks each step.
+    pub fn with_walk(&self, walk: WalkStrategy) -> Choices {
+        Choices { walk, ..self.clone() }
+    }
+
+    /// Scores relatives with the named profile from [`MonsterWrangler::profiles`]
+    /// instead of the one the graph was loaded with.
+    pub fn with_profile(&self, profile: Option<String>) -> Choices {
+        Choices {
+            profile,
+            ..self.clone()
         }
     }
 
+    /// Makes every random decision repeatable: the same choices with the same seed pick
+    /// the same monsters.
+    pub fn with_seed(&self, seed: Option<u64>) -> Choices {
+        Choices { seed, ..self.clone() }
+    }
+
+    /// The biomes that would still match something if the wanted biomes were cleared.
+    /// Excluded biomes stay excluded.
     pub fn biomes(&self, wrangler: &MonsterWrangler) -> Vec<String> {
-        let choices = self.with_biome(String::new());
+        let choices = self.with_biomes(ValueFilter {
+            values: Vec::new(),
+            ..self.biomes.clone()
+        });
         let mut all = choices.filter(&wrangler.monsters, |monster: &&Monster| {
-            monster.biomes.clone()
+            monster
+                .biomes
+                .iter()
+                .flat_map(|b| biome::ancestors(b).into_iter().map(|a| a.to_string()).chain([b.clone()]))
+                .collect()
         });
         all.sort_unstable();
         all = all
             .iter()
-            .filter(|biome| !biome.eq(&"*"))
-            .map(|biome| biome.clone())
+            .filter(|biome| !biome.eq(&biome::ANY) && !self.biomes.excluded.contains(biome))
+            .cloned()
             .collect();
         all.dedup();
         all
     }
 
+    /// The tags that would still match something if the wanted tags were cleared.
+    /// Excluded tags stay excluded.
     pub fn tags(&self, wrangler: &MonsterWrangler) -> Vec<String> {
-        let choices = self.with_tag(String::new());
+        let choices = self.with_tags(ValueFilter {
+            values: Vec::new(),
+            ..self.tags.clone()
+        });
         let mut all = choices.filter(&wrangler.monsters, |monster: &&Monster| {
             monster.tags.clone()
         });
+        all.retain(|tag| !self.tags.excluded.contains(tag));
         all.sort_unstable();
         all.dedup();
         all
     }
 
+    /// The sources that would still match something if the wanted sources were cleared,
+    /// in source order, including those disabled by default that could be asked for.
+    /// Excluded sources stay excluded.
+    pub fn sources(&self, wrangler: &MonsterWrangler) -> Vec<String> {
+        let choices = self.with_sources(ValueFilter {
+            values: Vec::new(),
+            ..self.sources.clone()
+        });
+        let disabled = self.with_sources(ValueFilter {
+            values: sources::disabled(),
+            matching: filter::Match::Any,
+            ..self.sources.clone()
+        });
+        let source = |monster: &&Monster| vec![monster.source.clone()];
+        let mut all = choices.filter(&wrangler.monsters, source);
+        all.extend(disabled.filter(&wrangler.monsters, source));
+        all.sort_by(|a, b| sources::order(a).cmp(&sources::order(b)).then(a.cmp(b)));
+        all.dedup();
+        all
+    }
+
+    /// The alignments that would still match something if the wanted alignments were
+    /// cleared. Excluded alignments stay excluded.
+    pub fn alignments(&self, wrangler: &MonsterWrangler) -> Vec<String> {
+        let choices = self.with_alignments(ValueFilter {
+            values: Vec::new(),
+            ..self.alignments.clone()
+        });
+        let mut all = choices.filter(&wrangler.monsters, |monster: &&Monster| {
+            vec![monster.alignment.clone()]
+        });
+        all.sort_unstable();
+        all.dedup();
+        all
+    }
+
+    /// The movement modes that would still match something if the wanted modes were
+    /// cleared. Excluded modes stay excluded.
+    pub fn movement_modes(&self, wrangler: &MonsterWrangler) -> Vec<String> {
+        let choices = self.with_movement(ValueFilter {
+            values: Vec::new(),
+            ..self.movement.clone()
+        });
+        let mut all = choices.filter(&wrangler.monsters, |monster: &&Monster| {
+            monster.stat_block.movement_modes()
+        });
+        all.retain(|mode| !self.movement.excluded.contains(mode));
+        all.sort_unstable();
+        all.dedup();
+        all
+    }
+
+    /// The rarities that would still match something if the wanted rarities were
+    /// cleared, commonest first. Excluded rarities stay excluded.
+    pub fn rarities(&self, wrangler: &MonsterWrangler) -> Vec<String> {
+        let choices = self.with_rarities(ValueFilter {
+            values: Vec::new(),
+            ..self.rarities.clone()
+        });
+        let mut all = choices.filter(&wrangler.monsters, |monster: &&Monster| vec![monster.rarity]);
+        all.sort_unstable();
+        all.dedup();
+        all.iter().map(|rarity| rarity.to_string()).collect()
+    }
+
+    /// The levels that would still match something if the level filter were cleared.
     pub fn levels(&self, wrangler: &MonsterWrangler) -> Vec<u8> {
-        let choices = self.with_level(None);
+        let choices = self.with_levels(None);
         let mut all = choices.filter(&wrangler.monsters, |monster: &&Monster| vec![monster.level]);
         all.sort_unstable();
         all.dedup();
@@ -210,23 +768,13 @@ impl Choices {
         monsters
             .all()
             .iter()
-            .filter(|monster| match &self.biome {
-                None => true,
-                Some(biome) => monster.biomes.contains(biome),
-            })
-            .filter(|monster| match &self.level {
-                None => true,
-                Some(level) => monster.level == *level,
-            })
-            .filter(|monster| match &self.tag {
-                None => true,
-                Some(tag) => monster.tags.contains(tag),
-            })
+            .filter(|monster| self.matches(monster))
             .flat_map(x)
             .collect()
     }
 
-    pub(crate) fn state(&self) -> String {
+    /// A short description of the active filters, e.g. `level=3-5, biome=forest,swamp`.
+    pub fn state(&self) -> String {
         let mut result = "".to_string();
 
         // Add seed monster info if present
@@ -234,20 +782,20 @@ impl Choices {
             result.push_str(&format!("Seed: {}", monster.name));
         }
 
-        result = match &self.level {
+        result = match &self.levels {
             None => result,
             Some(x) => {
                 if result.is_empty() {
-                    result + &format!("level={}", x)
+                    result + &format!("level={}", filter::format_levels(x))
                 } else {
-                    result + &format!(", level={}", x)
+                    result + &format!(", level={}", filter::format_levels(x))
                 }
             }
         };
 
-        result = match &self.biome {
-            None => result,
-            Some(x) => {
+        result = match &self.biomes {
+            x if x.is_empty() => result,
+            x => {
                 if result.is_empty() {
                     result + &format!("biome={}", x)
                 } else {
@@ -256,9 +804,9 @@ impl Choices {
             }
         };
 
-        result = match &self.tag {
-            None => result,
-            Some(x) => {
+        result = match &self.tags {
+            x if x.is_empty() => result,
+            x => {
                 if result.is_empty() {
                     result + &format!("tag={}", x)
                 } else {
@@ -266,6 +814,83 @@ impl Choices {
                 }
             }
         };
+        result = match &self.sources {
+            x if x.is_empty() => result,
+            x => {
+                if result.is_empty() {
+                    result + &format!("source={}", x)
+                } else {
+                    result + &format!(", source={}", x)
+                }
+            }
+        };
+        result = match &self.alignments {
+            x if x.is_empty() => result,
+            x => {
+                if result.is_empty() {
+                    result + &format!("alignment={}", x)
+                } else {
+                    result + &format!(", alignment={}", x)
+                }
+            }
+        };
+        result = match &self.movement {
+            x if x.is_empty() => result,
+            x => {
+                if result.is_empty() {
+                    result + &format!("move={}", x)
+                } else {
+                    result + &format!(", move={}", x)
+                }
+            }
+        };
+        result = match &self.rarities {
+            x if x.is_empty() => result,
+            x => {
+                if result.is_empty() {
+                    result + &format!("rarity={}", x)
+                } else {
+                    result + &format!(", rarity={}", x)
+                }
+            }
+        };
+        result = match &self.walk {
+            WalkStrategy::Skip => result,
+            x => {
+                if result.is_empty() {
+                    result + &format!("walk={}", x)
+                } else {
+                    result + &format!(", walk={}", x)
+                }
+            }
+        };
+        if self.numbers {
+            result = if result.is_empty() {
+                "numbers=on".to_string()
+            } else {
+                result + ", numbers=on"
+            };
+        }
+        result = match &self.profile {
+            None => result,
+            Some(x) => {
+                if result.is_empty() {
+                    result + &format!("profile={}", x)
+                } else {
+                    result + &format!(", profile={}", x)
+                }
+            }
+        };
+        result = match &self.seed {
+            None => result,
+            Some(x) => {
+                if result.is_empty() {
+                    result + &format!("seed={}", x)
+                } else {
+                    result + &format!(", seed={}", x)
+                }
+            }
+        };
         if result.is_empty() {
             "[]".to_string()
         } else {
@@ -276,8 +901,68 @@ impl Choices {
 
 #[cfg(test)]
 mod tests {
-    use crate::monster_loader;
-    use crate::monster_wrangler::{Choices, MonsterWrangler};
+    use crate::biome;
+    use crate::data::{Monster, OutputFormat};
+    use crate::dice::Dice;
+    use crate::encounter::{Difficulty, Party};
+    use crate::filter::ValueFilter;
+    use crate::data::Monsters;
+    use crate::monster_loader::{self, Pack};
+    use crate::monster_wrangler::{Choices, MonsterWrangler, PickError, Stream};
+    use crate::rarity::Rarity;
+    use crate::scoring;
+    use crate::walk::WalkStrategy;
+    use rand::Rng;
+    use std::sync::OnceLock;
+
+    /// Every bundled monster, loaded once for the tests that need the real packs.
+    fn bundled() -> &'static MonsterWrangler {
+        static BUNDLED: OnceLock<MonsterWrangler> = OnceLock::new();
+        BUNDLED.get_or_init(|| MonsterWrangler::new(monster_loader::get_monster_graph(&[]).unwrap()))
+    }
+
+    /// A few swamp, forest and cave monsters: enough for the filters, walks and tables
+    /// without scoring every bundled monster against every other.
+    fn small() -> MonsterWrangler {
+        // Name, level, alignment, biomes, tags, movement and source.
+        const MONSTERS: [(&str, u8, &str, &str, &str, &str, &str); 20] = [
+            ("GOBLIN", 1, "C", "cave, swamp, forest", "humanoid, goblinoid", "near", "core"),
+            ("BANDIT", 1, "C", "forest, swamp", "humanoid", "near", "core"),
+            ("GIANT FROG", 1, "N", "swamp", "animal, amphibian", "near (swim)", "core"),
+            ("GIANT LEECH", 1, "N", "swamp", "vermin", "near (swim)", "cs1"),
+            ("GHOUL", 2, "C", "swamp, tomb", "undead", "near", "core"),
+            ("CROCODILE", 2, "N", "swamp, river", "animal, reptile", "near (swim)", "core"),
+            ("WILL-O'-WISP", 3, "C", "swamp, forest", "fey, spirit", "near (fly)", "cs1"),
+            ("LIZARDFOLK", 3, "N", "swamp", "humanoid, reptile", "near (swim)", "cs1"),
+            ("BOG HAG", 5, "C", "swamp", "fey, hag", "near (swim)", "cs1"),
+            ("SWAMP TROLL", 6, "C", "swamp", "giant, troll", "near", "core"),
+            ("BLACK DRAGON", 9, "C", "swamp", "dragon", "double near (fly)", "core"),
+            ("THE BOG KING", 12, "C", "swamp", "legendary, undead", "near", "cs1"),
+            ("MERFOLK", 2, "L", "river", "humanoid", "near (swim)", "core"),
+            ("GIANT OWL", 3, "L", "forest", "animal, bird", "double near (fly)", "core"),
+            ("PEGASUS", 3, "L", "forest", "beast", "double near (fly)", "cs1"),
+            ("UNICORN", 4, "L", "forest", "beast, fey", "double near", "core"),
+            ("TREANT", 7, "L", "forest", "plant", "near", "core"),
+            ("CAVE BEAR", 4, "N", "cave", "animal", "near", "core"),
+            ("PIXIE", 1, "N", "forest", "fey", "near (fly)", "stygian library"),
+            ("ELK", 2, "N", "forest", "animal", "double near", "stygian library"),
+        ];
+        let entries: Vec<String> = MONSTERS
+            .iter()
+            .map(|(name, level, alignment, biomes, tags, movement, source)| {
+                format!(
+                    r#"{{"name": "{}", "tags": "{}", "level": "{}", "biome": "{}", "alignment": "{}", "move": "", "attack": "", "page": "1", "source": "{}",
+                    "statblock": "AC 12, HP 9, ATK 1 claw +2 (1d6), MV {}, S +1, D +1, C +1, I -1, W +0, Ch -1, AL {}, LV {}"}}"#,
+                    name, tags, level, biomes, alignment, source, movement, alignment, level
+                )
+            })
+            .collect();
+        let pack = Pack {
+            file: "small.json".to_string(),
+            contents: format!("[{}]", entries.join(",")),
+        };
+        MonsterWrangler::new(Monsters::new(monster_loader::load_packs(&[pack]).unwrap()))
+    }
 
     #[test]
     fn state_empty() {
@@ -303,23 +988,387 @@ mod tests {
         assert_eq!(choices.state(), "tag=cheese");
     }
 
+    #[test]
+    fn state_source() {
+        let choices = Choices::default().with_source(String::from("core"));
+        assert_eq!(choices.state(), "source=core");
+    }
+
     #[test]
     fn state_full() {
         let choices = Choices {
-            level: Some(4),
-            biome: Some(String::from("forest")),
-            tag: Some(String::from("cheese")),
-            randomness: None,
-            seed_monster: None,
+            levels: Some(4..=4),
+            biomes: ValueFilter::single("forest"),
+            tags: ValueFilter::single("cheese"),
+            ..Choices::default()
         };
         assert_eq!(choices.state(), "level=4, biome=forest, tag=cheese");
     }
 
+    #[test]
+    fn state_multiple_values() {
+        let choices = Choices::default()
+            .with_levels(Some(3..=5))
+            .with_biomes(ValueFilter::parse("forest,Swamp").unwrap())
+            .with_tags(ValueFilter::parse("undead+fey,-dragon").unwrap());
+        assert_eq!(choices.state(), "level=3-5, biome=forest,swamp, tag=undead+fey,-dragon");
+    }
+
+    #[test]
+    fn source_alignment_and_movement_filters() {
+        let wrangler = small();
+        let choices = wrangler
+            .choices()
+            .with_sources(ValueFilter::parse("core,cs1,-dolmenwood").unwrap())
+            .with_alignments(ValueFilter::parse("l").unwrap())
+            .with_movement(ValueFilter::parse("fly").unwrap());
+        assert_eq!(choices.state(), "source=core,cs1,-dolmenwood, alignment=L, move=fly");
+        let listed = wrangler.list(&choices);
+        assert!(!listed.is_empty());
+        for monster in &listed {
+            assert!(monster.source == "core" || monster.source == "cs1");
+            assert_eq!(monster.alignment, "L");
+            assert!(monster.stat_block.movement_modes().contains(&"fly".to_string()));
+        }
+        let sources = choices.sources(&wrangler);
+        assert!(sources.contains(&"cs1".to_string()));
+        assert!(!sources.contains(&"dolmenwood".to_string()));
+        assert!(choices.alignments(&wrangler).contains(&"C".to_string()));
+        assert!(choices.movement_modes(&wrangler).contains(&"swim".to_string()));
+    }
+
+    #[test]
+    fn disabled_sources_are_opt_in() {
+        let wrangler = small();
+        let listed = wrangler.list(&wrangler.choices());
+        assert_eq!(listed.len(), 18);
+        assert!(listed.iter().all(|m| m.source != "stygian library"));
+        let choices = wrangler.choices().with_sources(ValueFilter::parse("stygian library").unwrap());
+        let names: Vec<String> = wrangler.list(&choices).into_iter().map(|m| m.name).collect();
+        assert_eq!(names, vec!["PIXIE", "ELK"]);
+
+        // Ruling a source out doesn't bring back the ones disabled by default.
+        let choices = wrangler.choices().with_sources(ValueFilter::parse("-cs1").unwrap());
+        let listed = wrangler.list(&choices);
+        assert_eq!(listed.len(), 12);
+        assert!(listed.iter().all(|m| m.source == "core"));
+        assert_eq!(choices.sources(&wrangler), vec!["core", "stygian library"]);
+    }
+
+    #[test]
+    fn excluding_a_biome_keeps_related_ones() {
+        let wrangler = small();
+        let names = |biomes: &str| -> Vec<String> {
+            let choices = wrangler.choices().with_biomes(ValueFilter::parse(biomes).unwrap());
+            wrangler.list(&choices).into_iter().map(|m| m.name).collect()
+        };
+        assert_eq!(names("underground"), vec!["GOBLIN", "GHOUL", "CAVE BEAR"]);
+        assert_eq!(names("underground,-caves"), vec!["GHOUL"]);
+        assert_eq!(names("cave,-underground"), vec!["GOBLIN", "CAVE BEAR"]);
+        assert!(names("-underground").contains(&"CAVE BEAR".to_string()));
+        assert!(!names("-cave").contains(&"GOBLIN".to_string()));
+    }
+
+    #[test]
+    fn multi_value_filters() {
+        let wrangler = small();
+        let choices = wrangler
+            .choices()
+            .with_levels(Some(3..=5))
+            .with_biomes(ValueFilter::parse("forest,swamp").unwrap())
+            .with_tags(ValueFilter::parse("-undead").unwrap());
+        let listed = wrangler.list(&choices);
+        assert!(!listed.is_empty());
+        for monster in &listed {
+            assert!((3..=5).contains(&monster.level));
+            assert!(!monster.tags.contains(&"undead".to_string()));
+        }
+        assert!(!choices.tags(&wrangler).contains(&"undead".to_string()));
+        assert!(choices.levels(&wrangler).iter().any(|level| *level > 5));
+    }
+
+    #[test]
+    fn seeded_groups_repeat() {
+        let wrangler = small();
+        let choices = wrangler
+            .choices()
+            .with_biome(String::from("forest"))
+            .with_randomness(Some(3))
+            .with_seed(Some(7));
+        let names = |group: Vec<Monster>| group.iter().map(|m| m.name.clone()).collect::<Vec<_>>();
+        assert_eq!(
+            names(choices.cluster(5, &wrangler).unwrap()),
+            names(choices.cluster(5, &wrangler).unwrap())
+        );
+        assert_eq!(names(choices.walk(5, &wrangler).unwrap()), names(choices.walk(5, &wrangler).unwrap()));
+        assert_eq!(wrangler.rando(&choices).unwrap().name, wrangler.rando(&choices).unwrap().name);
+    }
+
+    #[test]
+    fn numbers_appearing() {
+        let wrangler = small();
+        let goblin = wrangler.find("goblin").unwrap();
+        assert_eq!(goblin.number_appearing, Dice::new(2, 4));
+        assert_eq!(wrangler.find("treant").unwrap().number_appearing, Dice::fixed(1));
+        assert_eq!(goblin.render_count(3, OutputFormat::Name), "3× GOBLIN");
+        assert!(goblin.render_count(3, OutputFormat::Html).starts_with("<b>3× GOBLIN</b>"));
+        assert_eq!(goblin.render_count(1, OutputFormat::Name), "GOBLIN");
+        assert!(goblin.render(OutputFormat::Standard).contains("\n\tATK 1 claw +2 (1d6, avg 3.5)\n"));
+
+        let choices = wrangler.choices().with_seed_monster(Some(goblin)).with_seed(Some(5));
+        let group = choices.cluster(5, &wrangler).unwrap();
+        assert!(choices.counted(group.clone()).iter().all(|(_, count)| *count == 1));
+        let choices = choices.with_numbers(true);
+        let counted = choices.counted(group);
+        assert_eq!(counted, choices.counted(choices.cluster(5, &wrangler).unwrap()));
+        for (monster, count) in &counted {
+            let dice = monster.number_appearing;
+            assert!((dice.min().max(1) as u32..=dice.max() as u32).contains(count));
+        }
+        assert!(counted.iter().any(|(_, count)| *count > 1));
+
+        let hit_points = choices.roll_hit_points(&counted);
+        for ((monster, count), hit_points) in counted.iter().zip(&hit_points) {
+            assert_eq!(hit_points.len(), *count as usize);
+            let most = monster.level.max(1) as i32 * 8 + monster.stat_block.abilities.constitution as i32;
+            assert!(hit_points.iter().all(|hp| *hp >= 1 && *hp as i32 <= most.max(1)));
+        }
+
+        // Counts and hit points don't replay the rolls that picked the monsters.
+        let first = |stream| choices.rng(stream).gen::<u64>();
+        let firsts = [Stream::Pick, Stream::Numbers, Stream::HitPoints, Stream::Table].map(first);
+        assert!(firsts.iter().enumerate().all(|(i, a)| firsts[i + 1..].iter().all(|b| a != b)));
+    }
+
+    #[test]
+    fn rarity_weights_picks() {
+        let wrangler = small();
+        assert_eq!(wrangler.find("goblin").unwrap().rarity, Rarity::Common);
+        assert_eq!(wrangler.find("black dragon").unwrap().rarity, Rarity::Rare);
+        assert_eq!(wrangler.find("the bog king").unwrap().rarity, Rarity::Unique);
+
+        // The Bog King is one of the swamp's monsters, but picked far less often than the rest.
+        let choices = wrangler.choices().with_biome(String::from("swamp"));
+        let all = wrangler.list(&choices);
+        let unique = all.iter().filter(|m| m.rarity == Rarity::Unique).count();
+        assert!(unique > 0 && unique < all.len());
+        let picks: Vec<Monster> = (0..200)
+            .map(|seed| wrangler.rando(&choices.with_seed(Some(seed))).unwrap())
+            .collect();
+        let picked_unique = picks.iter().filter(|m| m.rarity == Rarity::Unique).count();
+        assert!(picked_unique * all.len() < unique * picks.len());
+
+        let choices = choices.with_rarities(ValueFilter::parse("-Unique").unwrap());
+        assert_eq!(choices.state(), "biome=swamp, rarity=-unique");
+        assert_eq!(wrangler.list(&choices).len(), all.len() - unique);
+        assert!(!choices.rarities(&wrangler).contains(&"unique".to_string()));
+        for seed in 0..20 {
+            let table = choices.with_seed(Some(seed)).table(Dice::new(1, 6), &wrangler).unwrap();
+            assert!(table.entries.iter().all(|e| e.monster.rarity != Rarity::Unique));
+        }
+    }
+
+    #[test]
+    fn wandering_encounters() {
+        let wrangler = small();
+        let choices = wrangler.choices().with_biome(String::from("swamp")).with_seed(Some(8));
+        let wandering = choices.wandering(&wrangler).unwrap();
+        assert!(biome::matches(&wandering.monster.biomes, "swamp"));
+        assert_eq!(wandering.to_string(), choices.wandering(&wrangler).unwrap().to_string());
+
+        let goblin = wrangler.find("goblin").unwrap();
+        let choices = choices.with_seed_monster(Some(goblin.clone()));
+        assert_eq!(choices.wandering(&wrangler).unwrap().monster, goblin);
+        let nothing = choices.with_seed_monster(None).with_level(Some(40));
+        assert!(matches!(nothing.wandering(&wrangler), Err(PickError::NoMatches { .. })));
+    }
+
+    #[test]
+    fn walk_strategies() {
+        let wrangler = small();
+        let goblin = wrangler.find("goblin").unwrap();
+        let choices = wrangler.choices().with_seed_monster(Some(goblin)).with_seed(Some(3));
+        let walk = |strategy| choices.with_walk(strategy).walk(5, &wrangler).unwrap();
+
+        let ramp = walk(WalkStrategy::LevelRamp);
+        assert!(ramp.len() > 2);
+        assert!(ramp.windows(2).all(|pair| pair[0].level < pair[1].level));
+
+        let biome = walk(WalkStrategy::StayInBiome);
+        assert!(biome.iter().all(|m| biome::shared(&biome[0].biomes, &m.biomes) > 0));
+
+        let new_tags = walk(WalkStrategy::NewTags);
+        let tags: Vec<&String> = new_tags.iter().flat_map(|m| &m.tags).collect();
+        assert!(tags.iter().enumerate().all(|(i, tag)| !tags[i + 1..].contains(tag)));
+
+        // Near zero temperature always takes the strongest link.
+        let cold = walk(WalkStrategy::Softmax { temperature: 0.01 });
+        let graph = wrangler.graph(None).unwrap();
+        let view = choices.view(&graph);
+        for i in 1..cold.len() {
+            let visited = &cold[..i];
+            let strongest = view.neighbors(&cold[i - 1]).find(|(n, _)| !visited.contains(n)).unwrap();
+            assert_eq!(strongest.1.total, graph.explain(&cold[i - 1], &cold[i]).strength.total);
+        }
+        assert_eq!(walk(WalkStrategy::Weighted).len(), 6);
+        assert!(choices.with_walk(WalkStrategy::LevelRamp).state().contains("walk=ramp"));
+    }
+
+    #[test]
+    fn groups_stay_inside_the_filters() {
+        let wrangler = small();
+        let goblin = wrangler.find("goblin").unwrap();
+        let choices = wrangler
+            .choices()
+            .with_biome(String::from("swamp"))
+            .with_seed_monster(Some(goblin.clone()))
+            .with_randomness(Some(2))
+            .with_seed(Some(3));
+        for group in [choices.cluster(5, &wrangler).unwrap(), choices.walk(5, &wrangler).unwrap()] {
+            assert_eq!(group[0], goblin);
+            assert_eq!(group.len(), 6);
+            for monster in &group[1..] {
+                assert!(biome::matches(&monster.biomes, "swamp"), "{} isn't a swamp monster", monster.name);
+            }
+        }
+
+        let party = Party { size: 4, level: 3 };
+        let encounter = choices.encounter(party, Difficulty::Hard, &wrangler).unwrap();
+        assert_eq!(encounter.budget, 18);
+        assert!(encounter.total() <= encounter.budget);
+        for (monster, _) in &encounter.groups {
+            assert!(*monster == goblin || biome::matches(&monster.biomes, "swamp"));
+        }
+
+        let table = choices.table(Dice::new(2, 6), &wrangler).unwrap();
+        assert_eq!(table.entries.len(), 11);
+        assert!(table.entries.iter().all(|e| biome::matches(&e.monster.biomes, "swamp")));
+        assert!(table.entries[5].monster.level <= table.entries[0].monster.level);
+        let again = choices.table(Dice::new(2, 6), &wrangler).unwrap();
+        assert_eq!(again.entries[0].monster, table.entries[0].monster);
+        let (result, entry, count) = choices.roll_table(&table);
+        let (again_result, again_entry, again_count) = choices.roll_table(&again);
+        assert_eq!((again_result, &again_entry.monster, again_count), (result, &entry.monster, count));
+    }
+
+    #[test]
+    fn profiles_change_relatives() {
+        let wrangler = bundled();
+        let choices = wrangler
+            .choices()
+            .with_seed_monster(wrangler.find("goblin"))
+            .with_seed(Some(1));
+        let names = |choices: &Choices| {
+            choices.cluster(5, wrangler).unwrap().iter().map(|m| m.name.clone()).collect::<Vec<_>>()
+        };
+        let default = names(&choices);
+        assert_eq!(names(&choices.with_profile(Some(String::from("default")))), default);
+        assert_ne!(names(&choices.with_profile(Some(String::from("same-book")))), default);
+        let error = choices.with_profile(Some(String::from("gourmet"))).cluster(5, wrangler).unwrap_err();
+        assert!(matches!(error, PickError::UnknownProfile { .. }));
+    }
+
+    #[test]
+    fn rescoring_matches_a_fresh_build() {
+        let wrangler = small();
+        let same_book = scoring::built_in_profiles().into_iter().find(|p| p.name == "same-book").unwrap();
+        let rescored = wrangler.graph(Some("same-book")).unwrap();
+        let fresh = Monsters::with_profile(wrangler.monsters.all().into_iter().cloned().collect(), same_book);
+        let edges = |graph: &Monsters| {
+            let view = graph.view(None);
+            view.edges(1).into_iter().map(|(a, b, strength)| (a.id, b.id, strength.clone())).collect::<Vec<_>>()
+        };
+        assert_eq!(edges(&rescored), edges(&fresh));
+        assert_ne!(edges(&rescored), edges(&wrangler.monsters));
+    }
+
+    #[test]
+    fn explains_connections() {
+        let wrangler = bundled();
+        let goblin = wrangler.find("goblin").unwrap();
+        let bandit = wrangler.find("bandit").unwrap();
+        let connection = wrangler.explain(&wrangler.choices(), &goblin, &bandit).unwrap();
+        assert_eq!(connection.level_gap, 0);
+        assert_eq!(connection.shared_tags, vec!["humanoid"]);
+        assert_eq!(connection.strength.biome, 15);
+        assert_eq!(
+            connection.strength.total,
+            connection.strength.level
+                + connection.strength.tag
+                + connection.strength.biome
+                + connection.strength.alignment
+                + connection.strength.source
+                + connection.strength.text
+        );
+
+        let choices = wrangler.choices().with_biome(String::from("forest"));
+        let (strongest, weakest) = wrangler.links(&choices, &goblin, 3).unwrap();
+        assert_eq!(strongest.len(), 3);
+        assert_eq!(weakest.len(), 3);
+        assert!(strongest[0].strength.total >= weakest[2].strength.total);
+        assert!(weakest.iter().all(|connection| connection.strength.total > 0));
+    }
+
+    #[test]
+    fn paths_stay_inside_the_filters() {
+        let wrangler = bundled();
+        let goblin = wrangler.find("goblin").unwrap();
+        let vampire = wrangler.find("vampire").unwrap();
+        let choices = wrangler.choices().with_levels(Some(1..=6));
+        let path = wrangler.path(&choices, &goblin, &vampire).unwrap();
+        assert_eq!(path.first().unwrap().name, "GOBLIN");
+        assert_eq!(path.last().unwrap().name, "VAMPIRE");
+        assert!(path.len() > 2);
+        assert!(path[1..path.len() - 1].iter().all(|monster| (1..=6).contains(&monster.level)));
+
+        let choices = wrangler.choices().with_source(String::from("nowhere"));
+        assert!(matches!(
+            wrangler.path(&choices, &goblin, &vampire),
+            Err(PickError::NoPath { .. })
+        ));
+    }
+
+    #[test]
+    fn factions_split_the_filtered_monsters() {
+        let wrangler = bundled();
+        let choices = wrangler.choices().with_biome(String::from("swamp"));
+        let factions = wrangler.factions(&choices, 4).unwrap();
+        assert_eq!(factions.len(), 4);
+        assert!(factions.windows(2).all(|pair| pair[0].monsters.len() >= pair[1].monsters.len()));
+        let members: Vec<&Monster> = factions.iter().flat_map(|faction| &faction.monsters).collect();
+        assert!(members.iter().all(|monster| choices.matches(monster)));
+        for (i, monster) in members.iter().enumerate() {
+            assert!(!members[i + 1..].iter().any(|other| other.id == monster.id));
+        }
+
+        let choices = wrangler.choices().with_source(String::from("nowhere"));
+        assert!(matches!(wrangler.factions(&choices, 4), Err(PickError::NoMatches { .. })));
+    }
+
+    #[test]
+    fn empty_filters_suggest_relaxing() {
+        let wrangler = small();
+        let choices = wrangler
+            .choices()
+            .with_level(Some(1))
+            .with_tag(String::from("no-such-tag"));
+        let error = wrangler.rando(&choices).unwrap_err();
+        match &error {
+            PickError::NoMatches { filters, suggestions } => {
+                assert_eq!(filters, "level=1, tag=no-such-tag");
+                assert_eq!(suggestions.len(), 1);
+                assert_eq!(suggestions[0].0, "tag=no-such-tag");
+            }
+            _ => panic!("expected NoMatches, got {}", error),
+        }
+        assert!(choices.cluster(3, &wrangler).is_err());
+        assert!(choices.walk(3, &wrangler).is_err());
+    }
+
     #[test]
     fn no_empty_biomes() {
-        let monsters = monster_loader::get_monster_graph();
-        let wrangler = MonsterWrangler::new(monsters);
-        let biomes = wrangler.choices().biomes(&wrangler);
+        let wrangler = bundled();
+        let biomes = wrangler.choices().biomes(wrangler);
         assert!(!biomes.contains(&"".to_string()));
         assert!(!biomes.contains(&"*".to_string()));
     }
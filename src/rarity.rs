@@ -0,0 +1,108 @@
+//! How often a monster turns up, which weights random picks so that a unique villain
+//! isn't met as often as a pack of wolves.
+
+use std::fmt;
+
+/// How often a monster turns up, commonest first.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Rarity {
+    #[default]
+    Common,
+    Uncommon,
+    Rare,
+    /// A named individual, such as a legendary villain: there is only one of it.
+    Unique,
+}
+
+/// Every rarity, commonest first.
+pub const ALL: [Rarity; 4] = [Rarity::Common, Rarity::Uncommon, Rarity::Rare, Rarity::Unique];
+
+/// Tags that make a monster unique, rare or uncommon when its pack doesn't say. Checked
+/// in this order, so a legendary dragon is unique.
+const UNIQUE_TAGS: [&str; 2] = ["legendary", "artifact"];
+const RARE_TAGS: [&str; 9] = ["dragon", "celestial", "angel", "fiend", "demon", "devil", "outsider", "golem", "elemental"];
+const UNCOMMON_TAGS: [&str; 10] = [
+    "aberration",
+    "undead",
+    "fey",
+    "demi-fey",
+    "monstrosity",
+    "construct",
+    "ooze",
+    "dinosaur",
+    "magical beast",
+    "spirit",
+];
+
+impl Rarity {
+    pub fn parse(name: &str) -> Result<Rarity, String> {
+        match name.trim().to_lowercase().as_str() {
+            "common" => Ok(Rarity::Common),
+            "uncommon" => Ok(Rarity::Uncommon),
+            "rare" => Ok(Rarity::Rare),
+            "unique" => Ok(Rarity::Unique),
+            _ => Err(format!("unknown rarity '{}'; try common, uncommon, rare or unique", name)),
+        }
+    }
+
+    /// The rarity of a monster with these tags whose pack doesn't give one: unique for
+    /// legendary monsters, rare for dragons, fiends, celestials and the like, uncommon
+    /// for undead, fey, aberrations and other monstrosities, otherwise common.
+    pub fn infer(tags: &[String]) -> Rarity {
+        let tagged = |names: &[&str]| tags.iter().any(|tag| names.contains(&tag.as_str()));
+        if tagged(&UNIQUE_TAGS) {
+            Rarity::Unique
+        } else if tagged(&RARE_TAGS) {
+            Rarity::Rare
+        } else if tagged(&UNCOMMON_TAGS) {
+            Rarity::Uncommon
+        } else {
+            Rarity::Common
+        }
+    }
+
+    /// How likely a random pick is to land on a monster of this rarity, relative to the
+    /// others: a common monster is ten times as likely as a unique one.
+    pub fn weight(self) -> u32 {
+        match self {
+            Rarity::Common => 10,
+            Rarity::Uncommon => 5,
+            Rarity::Rare => 2,
+            Rarity::Unique => 1,
+        }
+    }
+}
+
+/// The rarity as [`Rarity::parse`] reads it.
+impl fmt::Display for Rarity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Rarity::Common => "common",
+            Rarity::Uncommon => "uncommon",
+            Rarity::Rare => "rare",
+            Rarity::Unique => "unique",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::rarity::{self, Rarity};
+
+    #[test]
+    fn parses_and_infers() {
+        for rarity in rarity::ALL {
+            assert_eq!(Rarity::parse(&rarity.to_string()), Ok(rarity));
+        }
+        assert_eq!(Rarity::parse("Rare"), Ok(Rarity::Rare));
+        assert!(Rarity::parse("mythic").is_err());
+
+        let tags = |tags: &[&str]| tags.iter().map(|t| t.to_string()).collect::<Vec<_>>();
+        assert_eq!(Rarity::infer(&tags(&["humanoid"])), Rarity::Common);
+        assert_eq!(Rarity::infer(&tags(&["undead"])), Rarity::Uncommon);
+        assert_eq!(Rarity::infer(&tags(&["undead", "dragon"])), Rarity::Rare);
+        assert_eq!(Rarity::infer(&tags(&["legendary", "outsider"])), Rarity::Unique);
+        assert!(Rarity::Common.weight() > Rarity::Unique.weight());
+    }
+}
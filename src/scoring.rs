@@ -0,0 +1,164 @@
+//! Scoring profiles: the weights that decide how strongly two monsters are related.
+//!
+//! Besides the built-in profiles, a `profiles.json` in the user pack directory can
+//! define more, or replace a built-in one by reusing its name. Each entry names the
+//! profile and any weights that differ from the default profile:
+//!
+//! ```json
+//! [{"name": "lairs", "biome": 20, "source": 0}]
+//! ```
+
+use crate::monster_loader::LoadError;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// The user file profiles are read from, inside the user pack directory.
+pub const PROFILES_FILE: &str = "profiles.json";
+
+/// How much each thing two monsters have in common adds to their connection strength.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ScoringProfile {
+    /// Empty when a user profile leaves it out, which [`load_profiles`] rejects rather
+    /// than letting the profile take the default's name and replace it.
+    #[serde(default)]
+    pub name: String,
+    /// The level score for monsters of the same level...
+    pub level: i32,
+    /// ...less this much for every level apart.
+    pub level_step: i32,
+    /// For sharing an alignment.
+    pub alignment: i32,
+    /// For each shared biome, up to `biome_cap` of them.
+    pub biome: i32,
+    pub biome_cap: i32,
+    /// For each shared tag.
+    pub tag: i32,
+    /// For coming from the same source.
+    pub source: i32,
+    /// For identical descriptions and abilities, scaled down by how different they are
+    /// (see [`crate::text`]).
+    pub text: i32,
+}
+
+impl Default for ScoringProfile {
+    fn default() -> Self {
+        ScoringProfile {
+            name: DEFAULT.to_string(),
+            level: 10,
+            level_step: 7,
+            alignment: 15,
+            biome: 5,
+            biome_cap: 3,
+            tag: 10,
+            source: 10,
+            text: 30,
+        }
+    }
+}
+
+/// The name of [`ScoringProfile::default`].
+pub const DEFAULT: &str = "default";
+
+/// The profiles every installation has, default first.
+pub fn built_in_profiles() -> Vec<ScoringProfile> {
+    let default = ScoringProfile::default();
+    vec![
+        default.clone(),
+        // Monsters that live together.
+        ScoringProfile {
+            name: "ecological".to_string(),
+            level_step: 4,
+            alignment: 5,
+            biome: 12,
+            tag: 5,
+            source: 0,
+            text: 10,
+            ..default.clone()
+        },
+        // Monsters of the same kind, wherever they live.
+        ScoringProfile {
+            name: "thematic".to_string(),
+            level: 5,
+            level_step: 3,
+            alignment: 10,
+            biome: 2,
+            biome_cap: 2,
+            tag: 20,
+            source: 0,
+            text: 60,
+        },
+        // Monsters from the same book.
+        ScoringProfile {
+            name: "same-book".to_string(),
+            level_step: 5,
+            alignment: 10,
+            source: 40,
+            text: 20,
+            ..default
+        },
+    ]
+}
+
+/// The built-in profiles followed by those in `path`. A user profile with the name of a
+/// built-in one replaces it. A missing file just means no user profiles.
+pub fn load_profiles(path: &Path) -> Result<Vec<ScoringProfile>, LoadError> {
+    let mut profiles = built_in_profiles();
+    if !path.exists() {
+        return Ok(profiles);
+    }
+    let file = path.display().to_string();
+    let contents = std::fs::read_to_string(path).map_err(|error| LoadError::Read {
+        file: file.clone(),
+        error,
+    })?;
+    let user: Vec<ScoringProfile> =
+        serde_json::from_str(&contents).map_err(|error| LoadError::Parse { file: file.clone(), error })?;
+    if user.iter().any(|profile| profile.name.is_empty()) {
+        return Err(LoadError::Parse {
+            file,
+            error: serde::de::Error::custom("every profile needs a name"),
+        });
+    }
+    for profile in user {
+        match profiles.iter_mut().find(|p| p.name == profile.name) {
+            Some(existing) => *existing = profile,
+            None => profiles.push(profile),
+        }
+    }
+    Ok(profiles)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::scoring::{self, ScoringProfile};
+
+    #[test]
+    fn user_profiles_fill_in_defaults() {
+        let dir = std::env::temp_dir().join(format!("randommon-profiles-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(scoring::PROFILES_FILE);
+        std::fs::write(&path, r#"[{"name": "lairs", "biome": 20}, {"name": "thematic", "tag": 50}]"#).unwrap();
+        let profiles = scoring::load_profiles(&path).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let names: Vec<&str> = profiles.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, vec!["default", "ecological", "thematic", "same-book", "lairs"]);
+        let lairs = &profiles[4];
+        assert_eq!(lairs.biome, 20);
+        assert_eq!(lairs.alignment, ScoringProfile::default().alignment);
+        assert_eq!(profiles[2].tag, 50);
+        assert_eq!(profiles[2].level, 10);
+    }
+
+    #[test]
+    fn user_profiles_need_a_name() {
+        let dir = std::env::temp_dir().join(format!("randommon-nameless-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(scoring::PROFILES_FILE);
+        std::fs::write(&path, r#"[{"name": "lairs", "biome": 20}, {"biome": 0}]"#).unwrap();
+        let error = scoring::load_profiles(&path).unwrap_err();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert!(error.to_string().ends_with("every profile needs a name"));
+    }
+}
@@ -0,0 +1,150 @@
+//! The books and zines the bundled monsters come from.
+
+/// What we know about one source. `code` is the `source` field of its monsters.
+pub struct Source {
+    pub code: &'static str,
+    pub title: &'static str,
+    pub publisher: Option<&'static str>,
+    pub license: &'static str,
+    /// Stripped from the start of page references, e.g. `CS1-` in `CS1-46`.
+    pub page_prefix: &'static str,
+    /// Whether monsters from this source are picked unless a source filter says otherwise.
+    pub enabled: bool,
+}
+
+const OFFICIAL: &str = "Shadowdark RPG © 2023 The Arcane Library, LLC";
+const THIRD_PARTY: &str = "Shadowdark RPG Third-Party License";
+const CONVERTED: &str = "Converted from another game for personal use";
+
+/// Every bundled source in display order: Core, then Unnatural Selection, then the two
+/// Shadow Beasties, then the rest.
+pub const SOURCES: [Source; 16] = [
+    source("core", "Shadowdark RPG Core Rules", Some("The Arcane Library"), OFFICIAL, "", true),
+    source("us", "Unnatural Selection", None, THIRD_PARTY, "US-", true),
+    source("SB1", "Shadow Beasties 1", None, THIRD_PARTY, "SB1-", true),
+    source("SB2", "Shadow Beasties 2", None, THIRD_PARTY, "SB2-", true),
+    source("cs1", "Cursed Scroll 1", Some("The Arcane Library"), OFFICIAL, "CS1-", true),
+    source("cs2", "Cursed Scroll 2", Some("The Arcane Library"), OFFICIAL, "CS2-", true),
+    source("cs3", "Cursed Scroll 3", Some("The Arcane Library"), OFFICIAL, "CS3-", true),
+    source("cs4", "Cursed Scroll 4", Some("The Arcane Library"), OFFICIAL, "CS4-", true),
+    source("cs5", "Cursed Scroll 5", Some("The Arcane Library"), OFFICIAL, "CS5-", true),
+    source("GMC", "Gamemaster Companion", None, THIRD_PARTY, "GMC-", true),
+    source("AA", "Adventure Anthology", None, THIRD_PARTY, "AA-", true),
+    source("monday", "Monster Monday", None, THIRD_PARTY, "MM-", true),
+    source("DTS", "Dragontown", None, THIRD_PARTY, "DTS-", true),
+    source("dolmenwood", "Dolmenwood", Some("Necrotic Gnome"), CONVERTED, "DW-", true),
+    source("custom", "Custom monsters", None, "Homebrew", "", true),
+    source("stygian library", "The Stygian Library", None, CONVERTED, "", false),
+];
+
+const fn source(
+    code: &'static str,
+    title: &'static str,
+    publisher: Option<&'static str>,
+    license: &'static str,
+    page_prefix: &'static str,
+    enabled: bool,
+) -> Source {
+    Source {
+        code,
+        title,
+        publisher,
+        license,
+        page_prefix,
+        enabled,
+    }
+}
+
+/// The registered source with this code, ignoring case.
+pub fn find(code: &str) -> Option<&'static Source> {
+    SOURCES.iter().find(|source| source.code.eq_ignore_ascii_case(code))
+}
+
+/// Where the source sorts in listings. Sources from user packs that aren't registered
+/// come after every registered one.
+pub fn order(code: &str) -> usize {
+    SOURCES
+        .iter()
+        .position(|source| source.code.eq_ignore_ascii_case(code))
+        .unwrap_or(SOURCES.len())
+}
+
+/// The source's full title, or its code if it isn't registered.
+pub fn title(code: &str) -> String {
+    match find(code) {
+        Some(source) => source.title.to_string(),
+        None => code.to_string(),
+    }
+}
+
+/// Whether monsters from this source are picked by default. Sources from user packs that
+/// aren't registered are.
+pub fn enabled(code: &str) -> bool {
+    find(code).is_none_or(|source| source.enabled)
+}
+
+/// Sources whose monsters are left out by default.
+pub fn disabled() -> Vec<String> {
+    SOURCES
+        .iter()
+        .filter(|source| !source.enabled)
+        .map(|source| source.code.to_string())
+        .collect()
+}
+
+/// A page reference with the source's title, e.g. `Cursed Scroll 1 p. 46` for `CS1-46`.
+/// Pages from unregistered sources are returned as they are.
+pub fn reference(code: &str, page: &str) -> String {
+    let source = match find(code) {
+        Some(source) => source,
+        None => return page.to_string(),
+    };
+    let page = page.strip_prefix(source.page_prefix).unwrap_or(page);
+    if page.is_empty() || page == "N/A" {
+        source.title.to_string()
+    } else if page.chars().all(|c| c.is_ascii_digit()) {
+        format!("{} p. {}", source.title, page)
+    } else {
+        format!("{} {}", source.title, page)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::monster_loader;
+    use crate::sources;
+
+    #[test]
+    fn references() {
+        assert_eq!(sources::reference("cs1", "CS1-46"), "Cursed Scroll 1 p. 46");
+        assert_eq!(sources::reference("core", "194"), "Shadowdark RPG Core Rules p. 194");
+        assert_eq!(sources::reference("DTS", "DTS-N/A"), "Dragontown");
+        assert_eq!(sources::reference("dolmenwood", "DWMB-112"), "Dolmenwood DWMB-112");
+        assert_eq!(sources::reference("zine", "12"), "12");
+    }
+
+    #[test]
+    fn every_bundled_pack_is_registered() {
+        let dir = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("src");
+        let bundled: Vec<String> = monster_loader::built_in_packs().into_iter().map(|pack| pack.file).collect();
+        for path in monster_loader::discover_packs(&dir) {
+            let file = path.file_name().unwrap().to_string_lossy().to_string();
+            assert!(bundled.contains(&file), "{} is not a built-in pack", file);
+        }
+        let monsters = monster_loader::load_monsters(&[]).unwrap();
+        for monster in &monsters {
+            assert!(sources::find(&monster.source).is_some(), "{} has no source entry", monster.source);
+        }
+        for source in &sources::SOURCES {
+            assert!(monsters.iter().any(|m| m.source == source.code), "no monsters from {}", source.code);
+        }
+    }
+
+    #[test]
+    fn disabled_sources() {
+        assert_eq!(sources::disabled(), vec!["stygian library"]);
+        assert!(sources::enabled("dolmenwood"));
+        assert!(!sources::enabled("Stygian Library"));
+        assert!(sources::enabled("zine"));
+    }
+}
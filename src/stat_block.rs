@@ -0,0 +1,690 @@
+use crate::dice::Damage;
+use std::fmt;
+
+/// A parsed stat block.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StatBlock {
+    pub ac: u8,
+    pub armor: Option<String>,
+    /// `None` when the stat block gives a variable amount (`HP *`).
+    pub hp: Option<u16>,
+    /// Anything written after (or instead of) the number, like `(10)` or `variable (see Heads)`.
+    pub hp_note: Option<String>,
+    pub attacks: Vec<Attack>,
+    pub movement: Vec<Movement>,
+    pub abilities: AbilityModifiers,
+    pub alignment: Alignment,
+    /// `None` when the stat block gives a variable level (`LV *`).
+    pub level: Option<u8>,
+}
+
+/// One entry on a stat block's ATK line, e.g. `2 tentacle (near) +5 (1d8 + curse)`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Attack {
+    pub count: u8,
+    pub name: String,
+    pub range: Option<String>,
+    pub bonus: Option<i8>,
+    pub damage: Option<String>,
+    pub riders: Vec<String>,
+    /// True when this attack is an `or` alternative to the one before it rather than
+    /// being made alongside it.
+    pub alternative: bool,
+}
+
+/// One entry on a stat block's MV line, e.g. `double near (fly)`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Movement {
+    pub distance: String,
+    pub modes: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct AbilityModifiers {
+    pub strength: i8,
+    pub dexterity: i8,
+    pub constitution: i8,
+    pub intelligence: i8,
+    pub wisdom: i8,
+    pub charisma: i8,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Alignment {
+    Lawful,
+    Neutral,
+    Chaotic,
+}
+
+/// Why a stat block couldn't be parsed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StatBlockError {
+    Missing(&'static str),
+    Invalid { field: &'static str, text: String },
+}
+
+impl fmt::Display for StatBlockError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            StatBlockError::Missing(field) => write!(f, "stat block has no {}", field),
+            StatBlockError::Invalid { field, text } => {
+                write!(f, "stat block has an invalid {}: '{}'", field, text)
+            }
+        }
+    }
+}
+
+impl Alignment {
+    pub fn from_code(code: &str) -> Option<Alignment> {
+        match code.trim() {
+            "L" => Some(Alignment::Lawful),
+            "N" => Some(Alignment::Neutral),
+            "C" => Some(Alignment::Chaotic),
+            _ => None,
+        }
+    }
+
+    pub fn code(&self) -> &'static str {
+        match self {
+            Alignment::Lawful => "L",
+            Alignment::Neutral => "N",
+            Alignment::Chaotic => "C",
+        }
+    }
+}
+
+const ABILITY_LABELS: [&str; 6] = ["S", "D", "C", "I", "W", "Ch"];
+
+impl AbilityModifiers {
+    fn set(&mut self, index: usize, value: i8) {
+        match index {
+            0 => self.strength = value,
+            1 => self.dexterity = value,
+            2 => self.constitution = value,
+            3 => self.intelligence = value,
+            4 => self.wisdom = value,
+            _ => self.charisma = value,
+        }
+    }
+
+    fn values(&self) -> [i8; 6] {
+        [
+            self.strength,
+            self.dexterity,
+            self.constitution,
+            self.intelligence,
+            self.wisdom,
+            self.charisma,
+        ]
+    }
+}
+
+impl StatBlock {
+    /// Parses a Shadowdark stat block such as
+    /// `AC 16, HP 39, ATK 2 tentacle (near) +5 (1d8 + curse), MV near (swim), S +4, D -1, C +3, I +4, W +2, Ch +2, AL C, LV 8`.
+    ///
+    /// Sections are located by their labels rather than by position, so missing commas,
+    /// commas inside attacks and an absent `MV` are all tolerated. Ability modifiers that
+    /// the stat block leaves out are treated as +0.
+    pub fn parse(full: &str) -> Result<StatBlock, StatBlockError> {
+        let words = top_level_words(full);
+        let find = |label: &'static str, from: usize| {
+            words.iter().position(|w| w.start >= from && w.text == label)
+        };
+
+        let ac = find("AC", 0).ok_or(StatBlockError::Missing("AC"))?;
+        let hp = find("HP", words[ac].end).ok_or(StatBlockError::Missing("HP"))?;
+        let atk = find("ATK", words[hp].end).ok_or(StatBlockError::Missing("ATK"))?;
+        let abilities = ability_start(full, &words, atk).ok_or(StatBlockError::Missing("ability modifiers"))?;
+        let mv = find("MV", words[atk].end).filter(|&mv| mv < abilities);
+        let al = words
+            .iter()
+            .position(|w| w.start >= words[abilities].start && w.text.starts_with("AL"))
+            .ok_or(StatBlockError::Missing("AL"))?;
+        let lv = find("LV", words[al].start).ok_or(StatBlockError::Missing("LV"))?;
+
+        let section = |from: usize, to: usize| trim_section(&full[from..to]);
+        let atk_end = mv.map(|mv| words[mv].start).unwrap_or(words[abilities].start);
+        let (ac, armor) = parse_ac(section(words[ac].end, words[hp].start))?;
+        let (hp, hp_note) = parse_hp(section(words[hp].end, words[atk].start))?;
+
+        Ok(StatBlock {
+            ac,
+            armor,
+            hp,
+            hp_note,
+            attacks: parse_attacks(section(words[atk].end, atk_end)),
+            movement: mv
+                .map(|mv| parse_movement(section(words[mv].end, words[abilities].start)))
+                .unwrap_or_default(),
+            abilities: parse_abilities(section(words[abilities].start, words[al].start))?,
+            alignment: parse_alignment(&full[words[al].start + 2..words[lv].start])?,
+            level: parse_variable("LV", section(words[lv].end, full.len()))?,
+        })
+    }
+
+    pub fn ac_text(&self) -> String {
+        match &self.armor {
+            Some(armor) => format!("AC {} ({})", self.ac, armor),
+            None => format!("AC {}", self.ac),
+        }
+    }
+
+    pub fn hp_text(&self) -> String {
+        match (self.hp, &self.hp_note) {
+            (Some(hp), Some(note)) => format!("HP {} {}", hp, note),
+            (Some(hp), None) => format!("HP {}", hp),
+            (None, Some(note)) => format!("HP {}", note),
+            (None, None) => "HP *".to_string(),
+        }
+    }
+
+    pub fn attack_text(&self) -> String {
+        let mut out = "ATK".to_string();
+        if self.attacks.is_empty() {
+            out.push_str(" none");
+        }
+        for (i, attack) in self.attacks.iter().enumerate() {
+            if i > 0 {
+                out.push_str(if attack.alternative { " or" } else { " and" });
+            }
+            out.push_str(&format!(" {}", attack));
+        }
+        out
+    }
+
+    pub fn movement_text(&self) -> String {
+        if self.movement.is_empty() {
+            return "MV none".to_string();
+        }
+        let movement: Vec<String> = self.movement.iter().map(|m| m.to_string()).collect();
+        format!("MV {}", movement.join(", "))
+    }
+
+    pub fn stats_text(&self) -> String {
+        let stats: Vec<String> = ABILITY_LABELS
+            .iter()
+            .zip(self.abilities.values())
+            .map(|(label, value)| format!("{} {:+}", label, value))
+            .collect();
+        stats.join(", ")
+    }
+
+    /// Every movement mode (fly, swim, climb...) across all of the movement entries.
+    pub fn movement_modes(&self) -> Vec<String> {
+        self.movement.iter().flat_map(|m| m.modes.clone()).collect()
+    }
+}
+
+impl Attack {
+    /// The attack's damage and riders as a rollable expression, or `None` when it has no
+    /// damage dice.
+    pub fn damage_roll(&self) -> Option<Damage> {
+        let effects: Vec<&str> = self.damage.iter().chain(&self.riders).map(|s| s.as_str()).collect();
+        Damage::parse(&effects.join(" + ")).ok()
+    }
+}
+
+/// The attack as it appears on the ATK line, with the average damage of each hit after
+/// its damage.
+impl fmt::Display for Attack {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.count)?;
+        if !self.name.is_empty() {
+            write!(f, " {}", self.name)?;
+        }
+        if let Some(range) = &self.range {
+            write!(f, " ({})", range)?;
+        }
+        if let Some(bonus) = self.bonus {
+            write!(f, " {:+}", bonus)?;
+        }
+        let effects: Vec<&str> = self
+            .damage
+            .iter()
+            .chain(self.riders.iter())
+            .map(|s| s.as_str())
+            .collect();
+        match (effects.is_empty(), self.damage_roll()) {
+            (true, _) => {}
+            (false, Some(damage)) => write!(f, " ({}, avg {})", effects.join(" + "), damage.average())?,
+            (false, None) => write!(f, " ({})", effects.join(" + "))?,
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Movement {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.modes.is_empty() {
+            write!(f, "{}", self.distance)
+        } else {
+            write!(f, "{} ({})", self.distance, self.modes.join(", "))
+        }
+    }
+}
+
+struct Word<'a> {
+    text: &'a str,
+    start: usize,
+    end: usize,
+}
+
+/// Runs of letters outside of parentheses, with their byte offsets.
+fn top_level_words(s: &str) -> Vec<Word<'_>> {
+    let mut words = Vec::new();
+    let mut depth = 0;
+    let mut start = None;
+    for (i, c) in s.char_indices() {
+        if c.is_alphabetic() && depth == 0 {
+            start.get_or_insert(i);
+            continue;
+        }
+        if let Some(begin) = start.take() {
+            words.push(Word { text: &s[begin..i], start: begin, end: i });
+        }
+        match c {
+            '(' => depth += 1,
+            ')' => depth = (depth - 1).max(0),
+            _ => {}
+        }
+    }
+    if let Some(begin) = start {
+        words.push(Word { text: &s[begin..], start: begin, end: s.len() });
+    }
+    words
+}
+
+/// The first ability label after ATK that is followed by a modifier or by another label.
+fn ability_start(full: &str, words: &[Word], atk: usize) -> Option<usize> {
+    (atk + 1..words.len()).find(|&i| {
+        // A letter glued to a number is part of something else, like a 1D6.
+        if !ABILITY_LABELS.contains(&words[i].text) || full[..words[i].start].ends_with(|c: char| c.is_ascii_digit()) {
+            return false;
+        }
+        let rest = full[words[i].end..].trim_start();
+        let next_is_label = words
+            .get(i + 1)
+            .is_some_and(|w| ABILITY_LABELS.contains(&w.text) && full[words[i].end..w.start].trim().is_empty());
+        rest.starts_with(['+', '-']) || rest.starts_with(|c: char| c.is_ascii_digit()) || next_is_label
+    })
+}
+
+fn trim_section(s: &str) -> &str {
+    s.trim_matches(|c: char| c == ',' || c.is_whitespace())
+}
+
+fn parse_ac(text: &str) -> Result<(u8, Option<String>), StatBlockError> {
+    let invalid = || StatBlockError::Invalid { field: "AC", text: text.to_string() };
+    let digits: String = text.chars().take_while(|c| c.is_ascii_digit()).collect();
+    let ac = digits.parse().map_err(|_| invalid())?;
+    let rest = text[digits.len()..].trim();
+    if rest.is_empty() {
+        return Ok((ac, None));
+    }
+    match rest.strip_prefix('(').and_then(|r| r.strip_suffix(')')) {
+        Some(armor) => Ok((ac, Some(armor.trim().to_string()))),
+        None => Err(invalid()),
+    }
+}
+
+fn parse_hp(text: &str) -> Result<(Option<u16>, Option<String>), StatBlockError> {
+    let digits: String = text.chars().take_while(|c| c.is_ascii_digit()).collect();
+    let note = text[digits.len()..].trim();
+    let note = if note.is_empty() || note == "*" { None } else { Some(note.to_string()) };
+    match digits.parse() {
+        Ok(hp) => Ok((Some(hp), note)),
+        Err(_) if text.starts_with('*') || text.starts_with("variable") => Ok((None, note)),
+        Err(_) => Err(StatBlockError::Invalid { field: "HP", text: text.to_string() }),
+    }
+}
+
+/// A number, or `*`/`variable` for values that depend on the individual monster. A
+/// trailing asterisk on a number (`LV 3*`) is a footnote marker and is ignored.
+fn parse_variable<T: std::str::FromStr>(field: &'static str, text: &str) -> Result<Option<T>, StatBlockError> {
+    if text.starts_with('*') || text.starts_with("variable") {
+        return Ok(None);
+    }
+    let digits: String = text.chars().take_while(|c| c.is_ascii_digit()).collect();
+    match digits.parse() {
+        Ok(value) if text[digits.len()..].trim_start_matches('*').trim().is_empty() => Ok(Some(value)),
+        _ => Err(StatBlockError::Invalid { field, text: text.to_string() }),
+    }
+}
+
+fn parse_alignment(text: &str) -> Result<Alignment, StatBlockError> {
+    Alignment::from_code(trim_section(text))
+        .ok_or_else(|| StatBlockError::Invalid { field: "AL", text: trim_section(text).to_string() })
+}
+
+enum AbilityToken {
+    Label(usize),
+    Value(i8),
+}
+
+/// Labels and values are paired up in order. A value with no label in front of it
+/// belongs to the label straight after it when that label has no value of its own
+/// (`-2, I W -2`), otherwise to the next ability in S, D, C, I, W, Ch order.
+fn parse_abilities(text: &str) -> Result<AbilityModifiers, StatBlockError> {
+    let invalid = || StatBlockError::Invalid { field: "ability modifiers", text: text.to_string() };
+    let mut tokens = Vec::new();
+    let mut chars = text.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        if c.is_alphabetic() {
+            let mut end = i + c.len_utf8();
+            while let Some(&(j, next)) = chars.peek() {
+                if !next.is_alphabetic() {
+                    break;
+                }
+                end = j + next.len_utf8();
+                chars.next();
+            }
+            let label = ABILITY_LABELS.iter().position(|l| *l == &text[i..end]).ok_or_else(invalid)?;
+            tokens.push(AbilityToken::Label(label));
+        } else if c == '+' || c == '-' || c.is_ascii_digit() {
+            let mut number = c.to_string();
+            while let Some(&(_, next)) = chars.peek() {
+                if !next.is_ascii_digit() {
+                    break;
+                }
+                number.push(next);
+                chars.next();
+            }
+            let value = number.parse().map_err(|_| invalid())?;
+            tokens.push(AbilityToken::Value(value));
+        } else if c != ',' && !c.is_whitespace() {
+            return Err(invalid());
+        }
+    }
+
+    let mut modifiers = AbilityModifiers::default();
+    let mut assigned = [false; 6];
+    let mut pending = None;
+    let mut last = None;
+    let mut i = 0;
+    while i < tokens.len() {
+        match tokens[i] {
+            AbilityToken::Label(label) => pending = Some(label),
+            AbilityToken::Value(value) => {
+                let target = match pending.take() {
+                    Some(label) => label,
+                    None => match (tokens.get(i + 1), tokens.get(i + 2)) {
+                        (Some(AbilityToken::Label(label)), next) if !matches!(next, Some(AbilityToken::Value(_))) => {
+                            i += 1;
+                            *label
+                        }
+                        _ => {
+                            let after = last.map(|l| l + 1).unwrap_or(0);
+                            (after..6).find(|&l| !assigned[l]).ok_or_else(invalid)?
+                        }
+                    },
+                };
+                modifiers.set(target, value);
+                assigned[target] = true;
+                last = Some(target);
+            }
+        }
+        i += 1;
+    }
+    Ok(modifiers)
+}
+
+/// Parses the text of an MV entry, e.g. `close; double near (fly)`.
+pub fn parse_movement(text: &str) -> Vec<Movement> {
+    if text.eq_ignore_ascii_case("none") {
+        return Vec::new();
+    }
+    split_top_level(text, &[',', ';'])
+        .into_iter()
+        .map(|(piece, _)| {
+            let (distance, modes) = match piece.find('(') {
+                Some(open) => (&piece[..open], piece[open + 1..].trim_end_matches(')')),
+                None => (piece, ""),
+            };
+            Movement {
+                distance: distance.trim().to_lowercase(),
+                modes: modes
+                    .split([',', '/'])
+                    .flat_map(|mode| mode.split(" and "))
+                    .map(movement_mode)
+                    .filter(|mode| !mode.is_empty())
+                    .collect(),
+            }
+        })
+        .collect()
+}
+
+/// A movement mode in lower case with single spaces, without any distance that follows
+/// it (`swim near` is just `swim`).
+fn movement_mode(text: &str) -> String {
+    let mut words: Vec<String> = text.split_whitespace().map(|w| w.to_lowercase()).collect();
+    while words.last().is_some_and(|w| matches!(w.as_str(), "close" | "near" | "far")) {
+        words.pop();
+    }
+    words.join(" ")
+}
+
+/// Splits on the given separators and on the words `and`/`or`, ignoring anything in
+/// parentheses. Each piece is paired with whether an `or` came before it.
+fn split_top_level<'a>(text: &'a str, separators: &[char]) -> Vec<(&'a str, bool)> {
+    let mut pieces = Vec::new();
+    let mut depth = 0;
+    let mut start = 0;
+    let mut alternative = false;
+    let push = |piece: &'a str, alternative: &mut bool, pieces: &mut Vec<(&'a str, bool)>| {
+        let piece = piece.trim();
+        if !piece.is_empty() {
+            pieces.push((piece, *alternative));
+            *alternative = false;
+        }
+    };
+    for (i, c) in text.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth = (depth - 1).max(0),
+            _ if depth == 0 && separators.contains(&c) => {
+                push(&text[start..i], &mut alternative, &mut pieces);
+                start = i + 1;
+            }
+            ' ' if depth == 0 => {
+                let rest = &text[i + 1..];
+                for (word, is_or) in [("or ", true), ("and ", false)] {
+                    if rest.starts_with(word) {
+                        push(&text[start..i], &mut alternative, &mut pieces);
+                        alternative |= is_or;
+                        start = i + 1 + word.len();
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    push(&text[start..], &mut alternative, &mut pieces);
+    pieces
+}
+
+fn parse_attacks(text: &str) -> Vec<Attack> {
+    if text.eq_ignore_ascii_case("none") {
+        return Vec::new();
+    }
+    let mut attacks = Vec::new();
+    // A leading "or" is left over from ", or" so that has to count for the next piece.
+    let mut pending_or = false;
+    for (piece, alternative) in split_top_level(text, &[',']) {
+        let piece = match piece.strip_prefix("or ") {
+            Some(rest) => {
+                pending_or = true;
+                rest
+            }
+            None => piece,
+        };
+        let mut attack = parse_attack(piece);
+        attack.alternative = (alternative || pending_or) && !attacks.is_empty();
+        pending_or = false;
+        attacks.push(attack);
+    }
+    attacks
+}
+
+fn parse_attack(piece: &str) -> Attack {
+    let digits: String = piece.chars().take_while(|c| c.is_ascii_digit()).collect();
+    let (count, mut rest) = match digits.parse() {
+        Ok(count) if piece[digits.len()..].starts_with(' ') => (count, piece[digits.len()..].trim_start()),
+        _ => (1, piece),
+    };
+
+    let name_end = rest
+        .char_indices()
+        .find(|&(i, c)| c == '(' || ((c == '+' || c == '-') && rest[i + 1..].starts_with(|d: char| d.is_ascii_digit())))
+        .map(|(i, _)| i)
+        .unwrap_or(rest.len());
+    let mut attack = Attack {
+        count,
+        name: rest[..name_end].trim().to_string(),
+        range: None,
+        bonus: None,
+        damage: None,
+        riders: Vec::new(),
+        alternative: false,
+    };
+    rest = rest[name_end..].trim_start();
+
+    while !rest.is_empty() {
+        if let Some(inner) = rest.strip_prefix('(') {
+            let close = matching_paren(inner);
+            classify_parenthetical(&inner[..close], &mut attack);
+            rest = inner[(close + 1).min(inner.len())..].trim_start();
+        } else if rest.starts_with(['+', '-']) {
+            let digits: String = rest[1..].chars().take_while(|c| c.is_ascii_digit()).collect();
+            let bonus: i8 = digits.parse().unwrap_or(0);
+            attack.bonus = Some(if rest.starts_with('-') { -bonus } else { bonus });
+            rest = rest[1 + digits.len()..].trim_start();
+        } else {
+            let end = rest.find('(').unwrap_or(rest.len());
+            attack.riders.push(rest[..end].trim().to_string());
+            rest = &rest[end..];
+        }
+    }
+    attack
+}
+
+fn matching_paren(inner: &str) -> usize {
+    let mut depth = 0;
+    for (i, c) in inner.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' if depth == 0 => return i,
+            ')' => depth -= 1,
+            _ => {}
+        }
+    }
+    inner.len()
+}
+
+fn classify_parenthetical(content: &str, attack: &mut Attack) {
+    for part in content.split(',').map(|p| p.trim()).filter(|p| !p.is_empty()) {
+        if is_range(part) && attack.range.is_none() {
+            attack.range = Some(part.to_string());
+            continue;
+        }
+        let terms: Vec<&str> = part.split('+').map(|t| t.trim()).collect();
+        let dice_terms = terms.iter().take_while(|t| is_dice_term(t)).count();
+        if dice_terms == 0 || attack.damage.is_some() {
+            attack.riders.push(part.to_string());
+            continue;
+        }
+        attack.damage = Some(terms[..dice_terms].join(" + "));
+        attack
+            .riders
+            .extend(terms[dice_terms..].iter().filter(|t| !t.is_empty()).map(|t| t.to_string()));
+    }
+}
+
+fn is_range(part: &str) -> bool {
+    part.split(['/', ' '])
+        .filter(|w| !w.is_empty())
+        .all(|w| matches!(w.to_lowercase().as_str(), "close" | "near" | "far" | "double" | "triple" | "half"))
+}
+
+/// `3`, `1d8` or `d6`.
+fn is_dice_term(term: &str) -> bool {
+    let (count, sides) = match term.split_once('d') {
+        Some(parts) => parts,
+        None => ("", term),
+    };
+    count.chars().all(|c| c.is_ascii_digit())
+        && !sides.is_empty()
+        && sides.chars().all(|c| c.is_ascii_digit())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::stat_block::{parse_movement, Alignment, StatBlock, StatBlockError};
+
+    #[test]
+    fn parses_full_stat_block() {
+        let block = StatBlock::parse("AC 17 (plate mail + shield), HP 39, ATK 2 tentacle (near) +5 (1d8 + curse) or 1 tail +5 (3d6), MV near (swim, fly), S +4, D -1, C+3, I +4, W +2, Ch +2, AL C, LV 8").unwrap();
+        assert_eq!(block.ac, 17);
+        assert_eq!(block.armor.as_deref(), Some("plate mail + shield"));
+        assert_eq!(block.hp, Some(39));
+        assert_eq!(block.attacks.len(), 2);
+        assert_eq!(block.attacks[0].count, 2);
+        assert_eq!(block.attacks[0].name, "tentacle");
+        assert_eq!(block.attacks[0].range.as_deref(), Some("near"));
+        assert_eq!(block.attacks[0].bonus, Some(5));
+        assert_eq!(block.attacks[0].damage.as_deref(), Some("1d8"));
+        assert_eq!(block.attacks[0].riders, vec!["curse"]);
+        let damage = block.attacks[0].damage_roll().unwrap();
+        assert_eq!((damage.min(), damage.max(), damage.average()), (1, 8, 4.5));
+        assert_eq!(damage.riders, vec!["curse"]);
+        assert!(block.attacks[1].alternative);
+        assert_eq!(block.movement[0].distance, "near");
+        assert_eq!(block.movement[0].modes, vec!["swim", "fly"]);
+        assert_eq!(block.abilities.constitution, 3);
+        assert_eq!(block.alignment, Alignment::Chaotic);
+        assert_eq!(block.level, Some(8));
+    }
+
+    #[test]
+    fn commas_inside_attacks() {
+        let block = StatBlock::parse("AC 15, HP 15, ATK 2 flaming warhammer +3 (1d10, ignites flammables) or 1 crossbow (far) +0 (1d6), MV near, S +3, D +0, C+2, I +0, W +0, Ch +0, AL L, LV 3").unwrap();
+        assert_eq!(block.attacks[0].damage.as_deref(), Some("1d10"));
+        assert_eq!(block.attacks[0].riders, vec!["ignites flammables"]);
+        assert_eq!(block.attacks[1].range.as_deref(), Some("far"));
+        assert_eq!(
+            block.attack_text(),
+            "ATK 2 flaming warhammer +3 (1d10 + ignites flammables, avg 5.5) or 1 crossbow (far) +0 (1d6, avg 3.5)"
+        );
+    }
+
+    #[test]
+    fn sloppy_separators() {
+        let block = StatBlock::parse("AC 12, HP 16, ATK 1 bite +3 (1d8) MV near (climb) S D +1, C +3, -2, I W -2, Ch -2, ALN, LV 3*").unwrap();
+        assert_eq!(block.movement[0].modes, vec!["climb"]);
+        assert_eq!(parse_movement("close (swim near)")[0].modes, vec!["swim"]);
+        assert_eq!(parse_movement("double near (run and climb)")[0].modes, vec!["run", "climb"]);
+        assert_eq!(parse_movement("near (burrow/spider climb)")[0].modes, vec!["burrow", "spider climb"]);
+        assert_eq!(block.stats_text(), "S +0, D +1, C +3, I -2, W -2, Ch -2");
+        assert_eq!(block.alignment, Alignment::Neutral);
+        assert_eq!(block.level, Some(3));
+    }
+
+    #[test]
+    fn variable_hp_and_level() {
+        let block = StatBlock::parse("AC 15, HP *, ATK 1 bite (near) +6 (1d8), MV near (swim), S +5, D +1, C +2, I -2, W +1, Ch -2, AL N, LV *").unwrap();
+        assert_eq!(block.hp, None);
+        assert_eq!(block.level, None);
+        let block = StatBlock::parse("AC 12, HP 19 or PC hp (whichever is higher), ATK 2 stinger +4 (1d8), MV near (fly), S +2, D +3, C +1, I +1, W +2, Ch +2, AL C, LV 4").unwrap();
+        assert_eq!(block.hp, Some(19));
+        assert_eq!(block.hp_text(), "HP 19 or PC hp (whichever is higher)");
+    }
+
+    #[test]
+    fn reports_errors() {
+        assert_eq!(StatBlock::parse("HP 4, ATK 1 bite, MV near, S +0, D +0, C +0, I +0, W +0, Ch +0, AL N, LV 1"), Err(StatBlockError::Missing("AC")));
+        assert_eq!(
+            StatBlock::parse("AC 12, HP 4, ATK 1 bite, MV near, S +0, D +0, C +0, I +0, W +0, Ch +0, AL X, LV 1"),
+            Err(StatBlockError::Invalid { field: "AL", text: "X".to_string() })
+        );
+    }
+}
@@ -0,0 +1,335 @@
+//! Random encounter tables: every result of a die (or dice, like `2d6`) leads to a
+//! monster, with the most common monsters on the likeliest results and the rarest and
+//! toughest on the extremes.
+
+use crate::data::Monster;
+use crate::dice::Dice;
+use rand::Rng;
+use serde::Serialize;
+use std::fmt::Write;
+
+/// The most results a table may have, so that `100d100` doesn't make a table of 9901.
+pub const MAX_RESULTS: i32 = 100;
+
+/// Reads the dice for a table: dice notation like `d8` or `2d6+1`, with at least one die
+/// of at least two sides and no more than [`MAX_RESULTS`] results.
+pub fn parse_dice(text: &str) -> Result<Dice, String> {
+    let dice = Dice::parse(text)?;
+    if dice.count == 0 || dice.sides < 2 {
+        return Err(format!("a table needs dice to roll, like d8 or 2d6, not '{}'", text));
+    }
+    if dice.max() - dice.min() >= MAX_RESULTS {
+        return Err(format!("'{}' has more than {} results for a table", text, MAX_RESULTS));
+    }
+    Ok(dice)
+}
+
+/// How [`EncounterTable::render`] lays a table out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TableFormat {
+    Markdown,
+    Html,
+    Json,
+}
+
+impl TableFormat {
+    pub fn parse(name: &str) -> Result<TableFormat, String> {
+        match name.to_lowercase().as_str() {
+            "markdown" | "md" => Ok(TableFormat::Markdown),
+            "html" => Ok(TableFormat::Html),
+            "json" => Ok(TableFormat::Json),
+            _ => Err(format!("Unknown table format '{}'; try markdown, html or json", name)),
+        }
+    }
+}
+
+/// One line of a table: the results that lead to it and the monster they lead to.
+#[derive(Debug, Clone)]
+pub struct TableEntry {
+    pub from: i32,
+    pub to: i32,
+    /// The chance of rolling this entry, from 0 to 1.
+    pub chance: f64,
+    pub monster: Monster,
+}
+
+impl TableEntry {
+    /// `4` or `4-5`.
+    pub fn results(&self) -> String {
+        if self.from == self.to {
+            self.from.to_string()
+        } else {
+            format!("{}-{}", self.from, self.to)
+        }
+    }
+}
+
+/// A rollable table of monsters, in the order of the results that lead to them.
+#[derive(Debug, Clone)]
+pub struct EncounterTable {
+    pub dice: Dice,
+    pub entries: Vec<TableEntry>,
+}
+
+impl EncounterTable {
+    /// Builds a table for `dice` from `monsters`, most common first. A single die gets
+    /// about half as many entries as it has sides, each covering two results except at
+    /// the ends; several dice already favor the middle, so each result gets an entry.
+    /// Either way there are no more entries than monsters. Fails without any monsters.
+    pub fn build(dice: Dice, monsters: &[Monster]) -> Option<EncounterTable> {
+        let chances = chances(dice);
+        let results = chances.len();
+        let count = EncounterTable::size(dice).min(monsters.len());
+        if count == 0 {
+            return None;
+        }
+
+        let mut spans = Vec::new();
+        let mut start = 0;
+        for width in widths(results, count) {
+            spans.push(start..start + width);
+            start += width;
+        }
+        // The likeliest spans first, nearer the middle on a tie.
+        let middle = (results as f64 - 1.0) / 2.0;
+        let mut order: Vec<usize> = (0..count).collect();
+        let chance = |i: usize| spans[i].clone().map(|r| chances[r]).sum::<f64>();
+        let distance = |i: usize| ((spans[i].start + spans[i].end - 1) as f64 / 2.0 - middle).abs();
+        order.sort_by(|a, b| chance(*b).total_cmp(&chance(*a)).then(distance(*a).total_cmp(&distance(*b))));
+
+        let mut entries: Vec<Option<TableEntry>> = vec![None; count];
+        for (monster, slot) in monsters.iter().zip(order) {
+            let span = &spans[slot];
+            entries[slot] = Some(TableEntry {
+                from: dice.min() + span.start as i32,
+                to: dice.min() + span.end as i32 - 1,
+                chance: chance(slot),
+                monster: monster.clone(),
+            });
+        }
+        Some(EncounterTable {
+            dice,
+            entries: entries.into_iter().flatten().collect(),
+        })
+    }
+
+    /// How many entries a table for `dice` has when there are enough monsters.
+    pub fn size(dice: Dice) -> usize {
+        let results = (dice.max() - dice.min()) as usize + 1;
+        if dice.count == 1 {
+            results / 2 + 1
+        } else {
+            results
+        }
+    }
+
+    /// Rolls the dice and then the number appearing of the entry they land on.
+    pub fn roll(&self, rng: &mut impl Rng) -> (i32, &TableEntry, u32) {
+        let result = self.dice.roll(rng);
+        let entry = self
+            .entries
+            .iter()
+            .find(|entry| (entry.from..=entry.to).contains(&result))
+            .expect("every result has an entry");
+        let count = entry.monster.number_appearing.roll(rng).max(1) as u32;
+        (result, entry, count)
+    }
+
+    pub fn render(&self, format: TableFormat) -> String {
+        match format {
+            TableFormat::Markdown => self.markdown(),
+            TableFormat::Html => self.html(),
+            TableFormat::Json => self.json(),
+        }
+    }
+
+    fn markdown(&self) -> String {
+        let mut out = format!("| {} | Monster | Number | LV | Source |\n|---|---|---|---|---|\n", self.dice);
+        for entry in &self.entries {
+            let monster = &entry.monster;
+            let _ = writeln!(
+                out,
+                "| {} | {} | {} | {} | {} |",
+                entry.results(),
+                monster.name,
+                monster.number_appearing,
+                monster.level,
+                monster.reference()
+            );
+        }
+        out
+    }
+
+    fn html(&self) -> String {
+        let escape = |text: &str| {
+            text.replace('&', "&amp;")
+                .replace('<', "&lt;")
+                .replace('>', "&gt;")
+                .replace('"', "&quot;")
+        };
+        let mut out = format!(
+            "<table>\n<tr><th>{}</th><th>Monster</th><th>Number</th><th>LV</th><th>Source</th></tr>\n",
+            self.dice
+        );
+        for entry in &self.entries {
+            let monster = &entry.monster;
+            let _ = writeln!(
+                out,
+                "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+                entry.results(),
+                escape(&monster.name),
+                monster.number_appearing,
+                monster.level,
+                escape(&monster.reference())
+            );
+        }
+        out.push_str("</table>\n");
+        out
+    }
+
+    fn json(&self) -> String {
+        #[derive(Serialize)]
+        struct JsonEntry<'a> {
+            from: i32,
+            to: i32,
+            chance: f64,
+            name: &'a str,
+            number_appearing: String,
+            rarity: String,
+            level: u8,
+            source: &'a str,
+            reference: String,
+        }
+        #[derive(Serialize)]
+        struct JsonTable<'a> {
+            dice: String,
+            entries: Vec<JsonEntry<'a>>,
+        }
+        let table = JsonTable {
+            dice: self.dice.to_string(),
+            entries: self
+                .entries
+                .iter()
+                .map(|entry| JsonEntry {
+                    from: entry.from,
+                    to: entry.to,
+                    chance: entry.chance,
+                    name: &entry.monster.name,
+                    number_appearing: entry.monster.number_appearing.to_string(),
+                    rarity: entry.monster.rarity.to_string(),
+                    level: entry.monster.level,
+                    source: &entry.monster.source,
+                    reference: entry.monster.reference(),
+                })
+                .collect(),
+        };
+        serde_json::to_string_pretty(&table).unwrap() + "\n"
+    }
+}
+
+/// The chance of each result of `dice`, lowest result first.
+fn chances(dice: Dice) -> Vec<f64> {
+    let mut ways = vec![1.0];
+    for _ in 0..dice.count {
+        let mut next = vec![0.0; ways.len() + dice.sides as usize - 1];
+        for (total, w) in ways.iter().enumerate() {
+            for face in 0..dice.sides as usize {
+                next[total + face] += w / dice.sides as f64;
+            }
+        }
+        ways = next;
+    }
+    ways
+}
+
+/// Splits `results` into `count` runs: one result at each end and the rest shared as
+/// evenly as possible, with any left over going to the runs nearest the middle.
+fn widths(results: usize, count: usize) -> Vec<usize> {
+    if count <= 2 {
+        return (0..count).map(|i| (results + i) / count).collect();
+    }
+    let inner = count - 2;
+    let mut widths = vec![(results - 2) / inner; inner];
+    let mut by_middle: Vec<usize> = (0..inner).collect();
+    by_middle.sort_by_key(|i| (2 * i).abs_diff(inner - 1));
+    for i in by_middle.into_iter().take((results - 2) % inner) {
+        widths[i] += 1;
+    }
+    let mut all = vec![1];
+    all.extend(widths);
+    all.push(1);
+    all
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::dice::Dice;
+    use crate::monster_loader;
+    use crate::table::{self, EncounterTable, TableFormat};
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn spans_and_chances() {
+        assert_eq!(table::widths(6, 4), vec![1, 2, 2, 1]);
+        assert_eq!(table::widths(12, 7), vec![1, 2, 2, 2, 2, 2, 1]);
+        assert_eq!(table::widths(8, 4), vec![1, 3, 3, 1]);
+        assert_eq!(table::widths(7, 4), vec![1, 3, 2, 1]);
+        assert_eq!(table::widths(6, 2), vec![3, 3]);
+        let chances = table::chances(Dice::new(2, 6));
+        assert_eq!(chances.len(), 11);
+        assert!((chances[5] - 6.0 / 36.0).abs() < 1e-9);
+        assert_eq!(table::parse_dice("d12"), Ok(Dice::new(1, 12)));
+        assert!(table::parse_dice("3").is_err());
+        assert!(table::parse_dice("1d1").is_err());
+        assert!(table::parse_dice("10d20").is_err());
+    }
+
+    #[test]
+    fn common_monsters_in_the_middle() {
+        let mut monsters = monster_loader::load_monsters(&[]).unwrap();
+        monsters.truncate(20);
+        monsters.sort_by_key(|m| m.level);
+        let table = EncounterTable::build(Dice::new(1, 8), &monsters).unwrap();
+        assert_eq!(table.entries.len(), 5);
+        assert_eq!((table.entries[0].from, table.entries[4].to), (1, 8));
+        assert_eq!(table.entries[2].monster.id, monsters[0].id);
+        assert!(table.entries[0].monster.level >= table.entries[2].monster.level);
+
+        let table = EncounterTable::build(Dice::new(2, 6), &monsters).unwrap();
+        assert_eq!(table.entries.len(), 11);
+        assert_eq!(table.entries[5].from, 7);
+        assert_eq!(table.entries[5].monster.id, monsters[0].id);
+        let total: f64 = table.entries.iter().map(|e| e.chance).sum();
+        assert!((total - 1.0).abs() < 1e-9);
+
+        let mut rng = StdRng::seed_from_u64(2);
+        for _ in 0..50 {
+            let (result, entry, count) = table.roll(&mut rng);
+            assert!((entry.from..=entry.to).contains(&result));
+            assert!(count >= 1);
+        }
+        let markdown = table.render(TableFormat::Markdown);
+        assert!(markdown.starts_with("| 2d6 | Monster |"));
+        assert_eq!(markdown.lines().count(), 13);
+        assert!(table.render(TableFormat::Html).contains("<td>7</td>"));
+        let json: serde_json::Value = serde_json::from_str(&table.render(TableFormat::Json)).unwrap();
+        assert_eq!(json["entries"].as_array().unwrap().len(), 11);
+
+        assert!(EncounterTable::build(Dice::new(1, 6), &[]).is_none());
+        assert_eq!(EncounterTable::build(Dice::new(1, 6), &monsters[..2]).unwrap().entries.len(), 2);
+    }
+
+    #[test]
+    fn html_escapes_names_and_sources() {
+        let mut monsters = monster_loader::load_monsters(&[]).unwrap();
+        monsters.truncate(2);
+        monsters[0].name = "FROG & <TOAD>".to_string();
+        monsters[0].source = "homebrew".to_string();
+        monsters[0].page = "\"Toads & Co\" p. 3".to_string();
+        let html = EncounterTable::build(Dice::new(1, 6), &monsters).unwrap().render(TableFormat::Html);
+        assert!(html.contains("<td>FROG &amp; &lt;TOAD&gt;</td>"));
+        assert!(html.contains("<td>&quot;Toads &amp; Co&quot; p. 3</td>"));
+        assert!(!html.contains("<TOAD>"));
+    }
+}
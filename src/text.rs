@@ -0,0 +1,157 @@
+//! Text similarity between monsters: TF-IDF over their names, descriptions, abilities
+//! and attack riders, compared by cosine similarity. Words that nearly every monster
+//! uses count for little, so what's left links monsters that share something unusual,
+//! like draining life or spitting poison.
+
+use crate::data::{Monster, MonsterId};
+use std::collections::HashMap;
+
+/// Words too common in stat blocks and rules text to say anything about a monster.
+const STOP_WORDS: [&str; 64] = [
+    "the", "and", "for", "that", "with", "its", "into", "from", "are", "this", "has", "have", "not", "can",
+    "any", "all", "one", "two", "three", "each", "per", "out", "who", "when", "while", "until", "then", "than",
+    "their", "they", "them", "also", "but", "only", "takes", "take", "make", "makes", "made", "within", "near",
+    "close", "far", "round", "rounds", "turn", "turns", "target", "targets", "creature", "creatures", "check",
+    "damage", "attack", "attacks", "hit", "str", "dex", "con", "int", "wis", "cha", "day", "place",
+];
+
+/// A monster's text as unit-length TF-IDF weights, sorted by term id.
+type Vector = Vec<(u32, f32)>;
+
+/// TF-IDF vectors for a set of monsters.
+#[derive(Default)]
+pub struct TextIndex {
+    terms: Vec<String>,
+    vectors: HashMap<MonsterId, Vector>,
+}
+
+impl TextIndex {
+    pub fn new<'a>(monsters: impl IntoIterator<Item = &'a Monster>) -> TextIndex {
+        let mut index = TextIndex::default();
+        let mut ids: HashMap<String, u32> = HashMap::new();
+        let mut counts: Vec<(MonsterId, HashMap<u32, f32>)> = Vec::new();
+        let mut document_frequency: HashMap<u32, f32> = HashMap::new();
+        for monster in monsters {
+            let mut count: HashMap<u32, f32> = HashMap::new();
+            for word in words(monster) {
+                let next = ids.len() as u32;
+                let id = *ids.entry(word.clone()).or_insert_with(|| {
+                    index.terms.push(word);
+                    next
+                });
+                *count.entry(id).or_default() += 1.0;
+            }
+            for id in count.keys() {
+                *document_frequency.entry(*id).or_default() += 1.0;
+            }
+            counts.push((monster.id, count));
+        }
+
+        let documents = counts.len() as f32;
+        for (monster, count) in counts {
+            let mut vector: Vector = count
+                .into_iter()
+                // A word only one monster uses can't link it to anything.
+                .filter(|(id, _)| document_frequency[id] > 1.0)
+                .map(|(id, n)| (id, (1.0 + n.ln()) * (documents / document_frequency[&id]).ln()))
+                .filter(|(_, weight)| *weight > 0.0)
+                .collect();
+            let norm = vector.iter().map(|(_, w)| w * w).sum::<f32>().sqrt();
+            vector.iter_mut().for_each(|(_, w)| *w /= norm);
+            vector.sort_by_key(|(id, _)| *id);
+            index.vectors.insert(monster, vector);
+        }
+        index
+    }
+
+    /// Cosine similarity from 0 (nothing unusual in common) to 1 (the same text).
+    pub fn similarity(&self, a: MonsterId, b: MonsterId) -> f32 {
+        self.shared(a, b).map(|(_, weight)| weight).sum()
+    }
+
+    /// Up to `count` of the words that do the most to make `a` and `b` similar.
+    pub fn shared_terms(&self, a: MonsterId, b: MonsterId, count: usize) -> Vec<String> {
+        let mut shared: Vec<(u32, f32)> = self.shared(a, b).collect();
+        shared.sort_by(|x, y| y.1.total_cmp(&x.1));
+        shared
+            .into_iter()
+            .take(count)
+            .map(|(id, _)| self.terms[id as usize].clone())
+            .collect()
+    }
+
+    /// The terms in both vectors, with what each adds to their dot product.
+    fn shared(&self, a: MonsterId, b: MonsterId) -> impl Iterator<Item = (u32, f32)> + '_ {
+        let empty: &[(u32, f32)] = &[];
+        let a = self.vectors.get(&a).map_or(empty, |v| v.as_slice());
+        let b = self.vectors.get(&b).map_or(empty, |v| v.as_slice());
+        let (mut i, mut j) = (0, 0);
+        std::iter::from_fn(move || {
+            while i < a.len() && j < b.len() {
+                let (x, y) = (a[i], b[j]);
+                if x.0 == y.0 {
+                    i += 1;
+                    j += 1;
+                    return Some((x.0, x.1 * y.1));
+                }
+                if x.0 < y.0 {
+                    i += 1;
+                } else {
+                    j += 1;
+                }
+            }
+            None
+        })
+    }
+}
+
+/// The words of everything a monster says about itself. Names and ability names count
+/// twice since they are the most telling.
+fn words(monster: &Monster) -> Vec<String> {
+    let mut text = vec![monster.name.clone(), monster.name.clone()];
+    text.extend(monster.description.clone());
+    for ability in monster.abilities.iter().flatten() {
+        text.push(ability.name.clone());
+        text.push(ability.name.clone());
+        text.push(ability.description.clone());
+    }
+    for attack in &monster.stat_block.attacks {
+        text.extend(attack.riders.iter().cloned());
+    }
+    text.iter().flat_map(|text| tokenize(text)).collect()
+}
+
+/// Lower-cased words of three or more letters, minus stop words and plural `s`.
+fn tokenize(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphabetic())
+        .map(|word| word.to_lowercase())
+        .filter(|word| word.chars().count() >= 3 && !STOP_WORDS.contains(&word.as_str()))
+        .map(|word| match word.strip_suffix('s') {
+            Some(stem) if stem.chars().count() >= 4 && !stem.ends_with('s') => stem.to_string(),
+            _ => word,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::monster_loader;
+    use crate::text::{tokenize, TextIndex};
+
+    #[test]
+    fn tokenizes_words() {
+        assert_eq!(tokenize("Drains the life of 1d4 creatures."), vec!["drain", "life"]);
+        assert_eq!(tokenize("Poison-spitting bass"), vec!["poison", "spitting", "bass"]);
+    }
+
+    #[test]
+    fn shared_themes_are_similar() {
+        let monsters = monster_loader::load_monsters(&[]).unwrap();
+        let index = TextIndex::new(&monsters);
+        let find = |name: &str| monsters.iter().find(|m| m.name == name).unwrap().id;
+        let (vampire, wight, goblin) = (find("VAMPIRE"), find("WIGHT"), find("GOBLIN"));
+        assert!(index.similarity(vampire, wight) > index.similarity(vampire, goblin));
+        assert!((index.similarity(vampire, vampire) - 1.0).abs() < 0.001);
+        assert!(!index.shared_terms(vampire, wight, 3).is_empty());
+    }
+}
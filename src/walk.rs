@@ -0,0 +1,146 @@
+//! How a walk chooses its next monster from the current one's relatives.
+
+use crate::biome;
+use crate::data::{Monster, View};
+use rand::distributions::WeightedIndex;
+use rand::prelude::*;
+use rand::rngs::StdRng;
+use std::fmt;
+
+/// The temperature [`WalkStrategy::Softmax`] uses when none is given.
+pub const DEFAULT_TEMPERATURE: f64 = 10.0;
+
+/// A rule for picking each step of a walk. Every strategy only steps to monsters the
+/// walk hasn't visited yet, and the walk ends when no relative qualifies.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum WalkStrategy {
+    /// Skip a random number (up to 9) of the closest relatives, or take the closest if
+    /// there aren't that many.
+    #[default]
+    Skip,
+    /// Any relative, with a chance in proportion to its strength.
+    Weighted,
+    /// Any relative, with a chance of `e^(strength / temperature)`: a low temperature
+    /// nearly always takes the strongest link, a high one treats them all alike.
+    Softmax { temperature: f64 },
+    /// Only relatives of a higher level than the current monster, weighted by strength.
+    LevelRamp,
+    /// Only relatives sharing a biome with the first monster, weighted by strength.
+    StayInBiome,
+    /// Only relatives with no tag any earlier monster had, weighted by strength.
+    NewTags,
+}
+
+impl WalkStrategy {
+    /// Reads a strategy name as [`fmt::Display`] writes it: `skip`, `weighted`,
+    /// `softmax` or `softmax:<temperature>`, `ramp`, `biome` or `new-tags`.
+    pub fn parse(text: &str) -> Result<WalkStrategy, String> {
+        let (name, temperature) = match text.split_once(':') {
+            Some((name, temperature)) => (name, Some(temperature)),
+            None => (text, None),
+        };
+        let strategy = match name.to_lowercase().as_str() {
+            "skip" => WalkStrategy::Skip,
+            "weighted" => WalkStrategy::Weighted,
+            "softmax" => WalkStrategy::Softmax {
+                temperature: match temperature {
+                    None => DEFAULT_TEMPERATURE,
+                    Some(t) => match t.parse::<f64>() {
+                        Ok(t) if t > 0.0 => t,
+                        _ => return Err(format!("softmax temperature must be a positive number, not '{}'", t)),
+                    },
+                },
+            },
+            "ramp" => WalkStrategy::LevelRamp,
+            "biome" => WalkStrategy::StayInBiome,
+            "new-tags" => WalkStrategy::NewTags,
+            _ => {
+                return Err(format!(
+                    "Unknown walk strategy '{}'; try skip, weighted, softmax, ramp, biome or new-tags",
+                    text
+                ))
+            }
+        };
+        if temperature.is_some() && !matches!(strategy, WalkStrategy::Softmax { .. }) {
+            return Err(format!("only softmax takes a temperature, not '{}'", text));
+        }
+        Ok(strategy)
+    }
+
+    /// The next monster for a walk that has visited `walk` so far, or `None` when the
+    /// last monster has no relatives this strategy allows.
+    pub fn step<'v>(&self, view: &'v View, walk: &[Monster], rng: &mut StdRng) -> Option<&'v Monster> {
+        let current = walk.last()?;
+        if *self == WalkStrategy::Skip {
+            let distance = rng.gen_range(1..10);
+            return view
+                .get_neighbor_excluding(current, walk, &distance)
+                .or_else(|| view.get_neighbor_excluding(current, walk, &1));
+        }
+
+        let candidates: Vec<(&Monster, i32)> = view
+            .neighbors(current)
+            .filter(|(neighbor, _)| !walk.iter().any(|m| m.id == neighbor.id))
+            .filter(|(neighbor, _)| self.allows(neighbor, walk))
+            .map(|(neighbor, strength)| (neighbor, strength.total))
+            .collect();
+        let strongest = candidates.first()?.1;
+        let weights: Vec<f64> = match self {
+            WalkStrategy::Softmax { temperature } => candidates
+                .iter()
+                .map(|(_, total)| ((total - strongest) as f64 / temperature).exp())
+                .collect(),
+            _ => candidates.iter().map(|(_, total)| *total as f64).collect(),
+        };
+        let index = WeightedIndex::new(&weights).ok()?.sample(rng);
+        Some(candidates[index].0)
+    }
+
+    /// Whether the strategy lets a walk that has visited `walk` step to `next`.
+    fn allows(&self, next: &Monster, walk: &[Monster]) -> bool {
+        match self {
+            WalkStrategy::LevelRamp => next.level > walk[walk.len() - 1].level,
+            WalkStrategy::StayInBiome => biome::shared(&walk[0].biomes, &next.biomes) > 0,
+            WalkStrategy::NewTags => !walk.iter().any(|m| m.tags.iter().any(|tag| next.tags.contains(tag))),
+            _ => true,
+        }
+    }
+}
+
+/// The strategy as [`WalkStrategy::parse`] reads it.
+impl fmt::Display for WalkStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            WalkStrategy::Skip => write!(f, "skip"),
+            WalkStrategy::Weighted => write!(f, "weighted"),
+            WalkStrategy::Softmax { temperature } => write!(f, "softmax:{}", temperature),
+            WalkStrategy::LevelRamp => write!(f, "ramp"),
+            WalkStrategy::StayInBiome => write!(f, "biome"),
+            WalkStrategy::NewTags => write!(f, "new-tags"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::walk::WalkStrategy;
+
+    #[test]
+    fn parses_strategies() {
+        assert_eq!(WalkStrategy::parse("ramp"), Ok(WalkStrategy::LevelRamp));
+        assert_eq!(
+            WalkStrategy::parse("softmax"),
+            Ok(WalkStrategy::Softmax { temperature: 10.0 })
+        );
+        assert_eq!(
+            WalkStrategy::parse("softmax:2.5"),
+            Ok(WalkStrategy::Softmax { temperature: 2.5 })
+        );
+        assert!(WalkStrategy::parse("softmax:0").is_err());
+        assert!(WalkStrategy::parse("ramp:3").is_err());
+        assert!(WalkStrategy::parse("sideways").is_err());
+        for text in ["skip", "weighted", "softmax:2.5", "ramp", "biome", "new-tags"] {
+            assert_eq!(WalkStrategy::parse(text).unwrap().to_string(), text);
+        }
+    }
+}
@@ -0,0 +1,255 @@
+//! Wandering monster encounters: a group of one kind of monster with everything the GM
+//! would otherwise roll by hand when it turns up — what it's doing, how far away it is,
+//! who is surprised, how it reacts and whether it will flee.
+
+use crate::data::{Monster, OutputFormat};
+use crate::dice::Dice;
+use rand::Rng;
+use std::fmt;
+
+/// The Wisdom check a monster must pass to keep fighting once it's down to half its
+/// number (or half its hit points, if alone).
+pub const MORALE_DC: i32 = 15;
+
+/// What the monsters are doing when the party meets them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Activity {
+    Hunting,
+    Lairing,
+    Travelling,
+}
+
+impl fmt::Display for Activity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Activity::Hunting => "hunting",
+            Activity::Lairing => "lairing",
+            Activity::Travelling => "travelling",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// How the monsters react to the party, from a 2d6 roll.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Reaction {
+    Hostile,
+    Suspicious,
+    Neutral,
+    Curious,
+    Friendly,
+}
+
+impl Reaction {
+    /// 6 or less is hostile, 7-8 suspicious, 9 neutral, 10-11 curious and 12 or more
+    /// friendly.
+    pub fn from_roll(total: i32) -> Reaction {
+        match total {
+            ..=6 => Reaction::Hostile,
+            7..=8 => Reaction::Suspicious,
+            9 => Reaction::Neutral,
+            10..=11 => Reaction::Curious,
+            _ => Reaction::Friendly,
+        }
+    }
+}
+
+impl fmt::Display for Reaction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Reaction::Hostile => "hostile",
+            Reaction::Suspicious => "suspicious",
+            Reaction::Neutral => "neutral",
+            Reaction::Curious => "curious",
+            Reaction::Friendly => "friendly",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// How far away the monsters are when either side notices the other.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Distance {
+    Close,
+    Near,
+    Far,
+}
+
+impl fmt::Display for Distance {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Distance::Close => "close",
+            Distance::Near => "near",
+            Distance::Far => "far",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// A wandering monster group and the rolls for meeting it. Each roll is kept alongside
+/// its outcome so the GM can see how it came about.
+#[derive(Debug, Clone)]
+pub struct Wandering {
+    pub monster: Monster,
+    pub count: u32,
+    pub activity: Activity,
+    pub distance: Distance,
+    pub party_surprised: bool,
+    pub monsters_surprised: bool,
+    /// The 2d6 reaction roll before [`Wandering::reaction_modifier`].
+    pub reaction_roll: i32,
+    pub reaction: Reaction,
+    /// The d20 roll plus the monster's Wisdom against [`MORALE_DC`], or `None` for
+    /// monsters immune to morale checks.
+    pub morale: Option<i32>,
+}
+
+impl Wandering {
+    /// Rolls how many of `monster` turn up and how the meeting goes: activity and
+    /// distance on a d6 each, surprise on a 1 or 2 of a d6 for each side, reaction on
+    /// 2d6 and morale on a d20.
+    pub fn roll(monster: &Monster, rng: &mut impl Rng) -> Wandering {
+        let count = monster.number_appearing.roll(rng).max(1) as u32;
+        let d6 = Dice::new(1, 6);
+        let activity = match d6.roll(rng) {
+            1..=2 => Activity::Hunting,
+            3..=4 => Activity::Travelling,
+            _ => Activity::Lairing,
+        };
+        let distance = match d6.roll(rng) {
+            1 => Distance::Close,
+            2..=4 => Distance::Near,
+            _ => Distance::Far,
+        };
+        let party_surprised = d6.roll(rng) <= 2;
+        let monsters_surprised = d6.roll(rng) <= 2;
+        let reaction_roll = Dice::new(2, 6).roll(rng);
+        let reaction = Reaction::from_roll(reaction_roll + Wandering::reaction_modifier(monster));
+        let morale = if fearless(monster) {
+            None
+        } else {
+            Some(Dice::new(1, 20).roll(rng) + monster.stat_block.abilities.wisdom as i32)
+        };
+        Wandering {
+            monster: monster.clone(),
+            count,
+            activity,
+            distance,
+            party_surprised,
+            monsters_surprised,
+            reaction_roll,
+            reaction,
+            morale,
+        }
+    }
+
+    /// Lawful monsters are more inclined to talk and chaotic ones to attack: +1 to the
+    /// reaction roll for `L`, -1 for `C`.
+    pub fn reaction_modifier(monster: &Monster) -> i32 {
+        match monster.alignment.as_str() {
+            "L" => 1,
+            "C" => -1,
+            _ => 0,
+        }
+    }
+
+    fn surprise(&self) -> &'static str {
+        match (self.party_surprised, self.monsters_surprised) {
+            (true, true) => "both sides are surprised",
+            (true, false) => "the party is surprised",
+            (false, true) => "the monsters are surprised",
+            (false, false) => "neither side is surprised",
+        }
+    }
+}
+
+/// Whether any of the monster's abilities make it immune to morale checks.
+fn fearless(monster: &Monster) -> bool {
+    monster
+        .abilities
+        .iter()
+        .flatten()
+        .any(|ability| ability.description.to_lowercase().contains("immune to morale"))
+}
+
+/// The encounter as a block to read at the table, without the monster's stat block.
+impl fmt::Display for Wandering {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let modifier = Wandering::reaction_modifier(&self.monster);
+        writeln!(
+            f,
+            "{}, {}, at {} range",
+            self.monster.render_count(self.count, OutputFormat::Name),
+            self.activity,
+            self.distance
+        )?;
+        writeln!(f, "Surprise: {}", self.surprise())?;
+        match modifier {
+            0 => writeln!(f, "Reaction: {} (2d6: {})", self.reaction, self.reaction_roll)?,
+            m => writeln!(
+                f,
+                "Reaction: {} (2d6: {} {:+} for alignment {} = {})",
+                self.reaction,
+                self.reaction_roll,
+                m,
+                self.monster.alignment,
+                self.reaction_roll + m
+            )?,
+        }
+        match self.morale {
+            None => write!(f, "Morale: fearless, never flees"),
+            Some(total) if total >= MORALE_DC => write!(
+                f,
+                "Morale: fights on at half strength (d20{:+} WIS: {} vs DC {})",
+                self.monster.stat_block.abilities.wisdom, total, MORALE_DC
+            ),
+            Some(total) => write!(
+                f,
+                "Morale: flees at half strength (d20{:+} WIS: {} vs DC {})",
+                self.monster.stat_block.abilities.wisdom, total, MORALE_DC
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::monster_loader;
+    use crate::wandering::{Reaction, Wandering};
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn rolls_a_meeting() {
+        assert_eq!(Reaction::from_roll(1), Reaction::Hostile);
+        assert_eq!(Reaction::from_roll(9), Reaction::Neutral);
+        assert_eq!(Reaction::from_roll(13), Reaction::Friendly);
+
+        let monsters = monster_loader::load_monsters(&[]).unwrap();
+        let find = |name: &str| monsters.iter().find(|m| m.name == name).unwrap();
+        let (goblin, cultist) = (find("GOBLIN"), find("CULTIST"));
+        assert_eq!(Wandering::reaction_modifier(goblin), -1);
+
+        let mut rng = StdRng::seed_from_u64(4);
+        let mut reactions = Vec::new();
+        for _ in 0..50 {
+            let wandering = Wandering::roll(goblin, &mut rng);
+            let dice = goblin.number_appearing;
+            assert!((dice.min() as u32..=dice.max() as u32).contains(&wandering.count));
+            assert!((2..=12).contains(&wandering.reaction_roll));
+            assert_eq!(wandering.reaction, Reaction::from_roll(wandering.reaction_roll - 1));
+            assert!(wandering.morale.is_some());
+            reactions.push(wandering.reaction);
+        }
+        assert!(reactions.contains(&Reaction::Hostile) && reactions.contains(&Reaction::Curious));
+
+        let wandering = Wandering::roll(cultist, &mut rng);
+        assert_eq!(wandering.morale, None);
+        let block = wandering.to_string();
+        assert!(block.contains("CULTIST, "));
+        assert!(block.contains(" range\n"));
+        assert!(block.contains("\nSurprise: "));
+        assert!(block.contains(&format!("for alignment C = {})", wandering.reaction_roll - 1)));
+        assert!(block.ends_with("Morale: fearless, never flees"));
+    }
+}